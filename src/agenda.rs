@@ -0,0 +1,204 @@
+//! Agenda view: upcoming events grouped by day
+//!
+//! Graph returns event times in the event's (or UTC) timezone; the agenda
+//! converts them to the mailbox timezone, or the system timezone with
+//! `--local`, before grouping.
+
+use chrono::{Duration, NaiveDateTime, NaiveTime};
+use clap::Args;
+use fafafa_outlook_core::{CalendarEvent, DateTimeTimeZone, OutlookClient};
+
+use crate::dates::{self, Clock};
+use crate::settings;
+
+#[derive(Args)]
+pub struct AgendaArgs {
+    /// First day to show (e.g. today, tomorrow, 'next monday', 2024-01-15)
+    #[arg(long, default_value = "today")]
+    start: String,
+    /// Number of days to show
+    #[arg(short, long, default_value = "7")]
+    days: u32,
+    /// Show times in the system timezone instead of the mailbox timezone
+    #[arg(long, conflicts_with = "timezone")]
+    local: bool,
+    /// Show times in this timezone (Windows or IANA name)
+    #[arg(long)]
+    timezone: Option<String>,
+    /// Maximum number of events to fetch
+    #[arg(short, long, default_value = "100")]
+    limit: u32,
+}
+
+/// An event with its times converted to the display timezone
+struct Entry {
+    start: NaiveDateTime,
+    end: Option<NaiveDateTime>,
+    all_day: bool,
+    event: CalendarEvent,
+}
+
+pub async fn show(client: &OutlookClient, args: AgendaArgs) -> anyhow::Result<()> {
+    let (clock, zone) = if args.local {
+        (Clock::local(), "local time".to_string())
+    } else {
+        let timezone = match args.timezone {
+            Some(tz) => tz,
+            None => settings::mailbox_timezone(client).await?,
+        };
+        (Clock::new(&timezone)?, timezone)
+    };
+
+    let first = clock.parse(&args.start)?.date();
+    let from = first.and_time(NaiveTime::MIN);
+    let to = from + Duration::days(args.days.max(1) as i64);
+    let events = client
+        .list_events_range(
+            &dates::format_utc(clock.to_utc(from)),
+            &dates::format_utc(clock.to_utc(to)),
+            args.limit,
+        )
+        .await?;
+
+    let mut entries: Vec<Entry> = events
+        .into_iter()
+        .filter_map(|event| {
+            let all_day = event.is_all_day.unwrap_or(false);
+            let start = convert(&clock, event.start.as_ref()?, all_day)?;
+            let end = event
+                .end
+                .as_ref()
+                .and_then(|end| convert(&clock, end, all_day));
+            Some(Entry {
+                start,
+                end,
+                all_day,
+                event,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| (e.start.date(), !e.all_day, e.start));
+
+    println!("Agenda ({})", zone);
+    if entries.is_empty() {
+        println!("No events");
+        return Ok(());
+    }
+    let mut current_day = None;
+    for entry in &entries {
+        let day = entry.start.date();
+        if current_day != Some(day) {
+            current_day = Some(day);
+            println!();
+            println!("{}", day.format("%a %Y-%m-%d"));
+        }
+        print_entry(entry);
+    }
+    Ok(())
+}
+
+/// Graph date/time in its own zone -> display wall-clock time. All-day
+/// events keep their date, since they are not tied to an instant.
+fn convert(clock: &Clock, dt: &DateTimeTimeZone, all_day: bool) -> Option<NaiveDateTime> {
+    if all_day {
+        return NaiveDateTime::parse_from_str(&dt.date_time, "%Y-%m-%dT%H:%M:%S%.f").ok();
+    }
+    clock.convert(dt)
+}
+
+fn print_entry(entry: &Entry) {
+    let event = &entry.event;
+    let when = if entry.all_day {
+        "All day".to_string()
+    } else {
+        match entry.end {
+            Some(end) => format!(
+                "{} - {}",
+                entry.start.format("%H:%M"),
+                if end.date() == entry.start.date() {
+                    end.format("%H:%M").to_string()
+                } else {
+                    end.format("%m-%d %H:%M").to_string()
+                }
+            ),
+            None => entry.start.format("%H:%M").to_string(),
+        }
+    };
+    let duration = match entry.end {
+        Some(end) if !entry.all_day => format!(" ({})", format_duration(end - entry.start)),
+        _ => String::new(),
+    };
+    let cancelled = if event.is_cancelled.unwrap_or(false) {
+        "[cancelled] "
+    } else {
+        ""
+    };
+    println!(
+        "  {}{}  {}{}",
+        when,
+        duration,
+        cancelled,
+        event.subject.as_deref().unwrap_or("(no subject)")
+    );
+
+    if let Some(location) = event
+        .location
+        .as_ref()
+        .and_then(|l| l.display_name.as_deref())
+        .filter(|l| !l.is_empty())
+    {
+        println!("      Location: {}", location);
+    }
+    if let Some(organizer) = &event.organizer {
+        let addr = &organizer.email_address;
+        match addr.name.as_deref().filter(|n| !n.is_empty()) {
+            Some(name) => println!("      Organizer: {} <{}>", name, addr.address),
+            None => println!("      Organizer: {}", addr.address),
+        }
+    }
+    if let Some(response) = event
+        .response_status
+        .as_ref()
+        .and_then(|r| r.response.as_deref())
+        .and_then(describe_response)
+    {
+        println!("      Response: {}", response);
+    }
+    if let Some(url) = event
+        .online_meeting
+        .as_ref()
+        .and_then(|m| m.join_url.as_deref())
+    {
+        println!("      Join: {}", url);
+    }
+}
+
+/// Graph responseType -> display text; `None` for "none"
+fn describe_response(response: &str) -> Option<&str> {
+    match response {
+        "organizer" => Some("organizer"),
+        "accepted" => Some("accepted"),
+        "tentativelyAccepted" => Some("tentative"),
+        "declined" => Some("declined"),
+        "notResponded" => Some("not responded"),
+        "none" => None,
+        other => Some(other),
+    }
+}
+
+/// `45m`, `1h`, `1h30m`, `2d`
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, mins) = (minutes / 1440, minutes % 1440 / 60, minutes % 60);
+    let mut out = String::new();
+    if days > 0 {
+        out.push_str(&format!("{}d", days));
+    }
+    if hours > 0 {
+        out.push_str(&format!("{}h", hours));
+    }
+    if mins > 0 || out.is_empty() {
+        out.push_str(&format!("{}m", mins));
+    }
+    out
+}
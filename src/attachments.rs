@@ -0,0 +1,304 @@
+//! Attachment downloads
+//!
+//! File attachments are streamed to disk through a `.part` file that is
+//! renamed into place once complete, so an interrupted download never
+//! leaves a truncated file behind and can be resumed on the next run. A
+//! `<file>.part.json` sidecar records which attachment the `.part` file
+//! holds; a partial file from a different attachment is never resumed.
+
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use fafafa_outlook_core::{Attachment, OutlookClient};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const ITEM_ATTACHMENT: &str = "#microsoft.graph.itemAttachment";
+const REFERENCE_ATTACHMENT: &str = "#microsoft.graph.referenceAttachment";
+
+/// Save every attachment of a message into `dir`; returns the files written
+pub async fn download_all(
+    client: &OutlookClient,
+    email_id: &str,
+    dir: &Path,
+    skip_inline: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut saved = Vec::new();
+    for att in client.list_attachments(email_id).await? {
+        if skip_inline && att.is_inline.unwrap_or(false) {
+            println!("  skip inline: {}", att.name);
+            continue;
+        }
+        match att.odata_type.as_deref() {
+            Some(REFERENCE_ATTACHMENT) => {
+                println!("  skip link: {}", att.name);
+            }
+            Some(ITEM_ATTACHMENT) => {
+                // Attached emails are exported as MIME
+                let bytes = client.export_item_attachment(email_id, &att.id).await?;
+                let path = unique_path(dir, &eml_name(&att));
+                std::fs::write(&path, &bytes)?;
+                println!("  {} ({} bytes)", path.display(), bytes.len());
+                saved.push(path);
+            }
+            _ => {
+                let path = unique_path(dir, &att.name);
+                let done = save_to_file(client, email_id, &att.id, &path).await?;
+                println!("  {} ({} bytes)", path.display(), done.bytes);
+                saved.push(path);
+            }
+        }
+    }
+    Ok(saved)
+}
+
+/// Size and SHA-256 of a finished download
+pub struct Downloaded {
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Stream an attachment to `path` via `<path>.part`, resuming a previous
+/// partial download when the server honors the range request
+pub async fn save_to_file(
+    client: &OutlookClient,
+    email_id: &str,
+    attachment_id: &str,
+    path: &Path,
+) -> anyhow::Result<Downloaded> {
+    let part = part_path(path);
+    let info_path = sidecar_path(&part);
+    let info = PartInfo::load(&info_path)
+        .filter(|info| info.email_id == email_id && info.attachment_id == attachment_id);
+    let existing = match &info {
+        Some(_) => std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0),
+        None => 0,
+    };
+    let mut stream = client
+        .download_attachment_stream(email_id, attachment_id, existing)
+        .await?;
+
+    let resume = existing > 0
+        && stream.resumed
+        && info.is_some_and(|info| info.total_size == stream.total_size)
+        && stream.total_size.is_none_or(|total| existing <= total);
+    if stream.resumed && !resume {
+        // The attachment changed since the partial download: start over
+        stream = client
+            .download_attachment_stream(email_id, attachment_id, 0)
+            .await?;
+    }
+
+    let mut hasher = Sha256::new();
+    let (mut file, offset) = if resume {
+        std::io::copy(&mut std::fs::File::open(&part)?, &mut hasher)?;
+        let file = std::fs::OpenOptions::new().append(true).open(&part)?;
+        (file, existing)
+    } else {
+        let file = std::fs::File::create(&part)?;
+        PartInfo {
+            email_id: email_id.to_string(),
+            attachment_id: attachment_id.to_string(),
+            total_size: stream.total_size,
+        }
+        .save(&info_path)?;
+        (file, 0)
+    };
+
+    let mut progress = Progress::new(stream.total_size, offset);
+    while let Some(chunk) = stream.next_chunk().await? {
+        file.write_all(&chunk)?;
+        hasher.update(&chunk);
+        progress.advance(chunk.len());
+    }
+    file.sync_all()?;
+    drop(file);
+    progress.finish();
+
+    std::fs::rename(&part, path)?;
+    let _ = std::fs::remove_file(&info_path);
+    Ok(Downloaded {
+        bytes: progress.done,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+/// Stream an attachment to stdout
+pub async fn save_to_stdout(
+    client: &OutlookClient,
+    email_id: &str,
+    attachment_id: &str,
+) -> anyhow::Result<Downloaded> {
+    let mut stream = client
+        .download_attachment_stream(email_id, attachment_id, 0)
+        .await?;
+    let mut hasher = Sha256::new();
+    let mut stdout = std::io::stdout();
+    let mut progress = Progress::new(stream.total_size, 0);
+    while let Some(chunk) = stream.next_chunk().await? {
+        stdout.write_all(&chunk)?;
+        hasher.update(&chunk);
+        progress.advance(chunk.len());
+    }
+    stdout.flush()?;
+    progress.finish();
+    Ok(Downloaded {
+        bytes: progress.done,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+fn sidecar_path(part: &Path) -> PathBuf {
+    let mut name = part.file_name().unwrap_or_default().to_os_string();
+    name.push(".json");
+    part.with_file_name(name)
+}
+
+/// The attachment a `.part` file belongs to
+#[derive(Serialize, Deserialize)]
+struct PartInfo {
+    email_id: String,
+    attachment_id: String,
+    total_size: Option<u64>,
+}
+
+impl PartInfo {
+    fn load(path: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        serde_json::from_str(&text).ok()
+    }
+
+    fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Progress bar on stderr, drawn only when stderr is a terminal
+struct Progress {
+    total: Option<u64>,
+    done: u64,
+    enabled: bool,
+    last_draw: Option<Instant>,
+}
+
+impl Progress {
+    fn new(total: Option<u64>, done: u64) -> Self {
+        Self {
+            total,
+            done,
+            enabled: std::io::stderr().is_terminal(),
+            last_draw: None,
+        }
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.done += n as u64;
+        let due = self
+            .last_draw
+            .is_none_or(|t| t.elapsed() >= Duration::from_millis(100));
+        if due {
+            self.draw();
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.enabled && self.last_draw.is_some() {
+            self.draw();
+            eprintln!();
+        }
+    }
+
+    fn draw(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        self.last_draw = Some(Instant::now());
+        let line = match self.total.filter(|t| *t > 0) {
+            Some(total) => {
+                let ratio = (self.done as f64 / total as f64).min(1.0);
+                let filled = (ratio * 30.0) as usize;
+                format!(
+                    "[{}{}] {:3.0}% {} / {}",
+                    "#".repeat(filled),
+                    " ".repeat(30 - filled),
+                    ratio * 100.0,
+                    human_size(self.done),
+                    human_size(total)
+                )
+            }
+            None => human_size(self.done),
+        };
+        eprint!("\r{}", line);
+    }
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+fn eml_name(att: &Attachment) -> String {
+    let name = att.name.trim();
+    let name = if name.is_empty() { "message" } else { name };
+    if name.to_lowercase().ends_with(".eml") {
+        name.to_string()
+    } else {
+        format!("{}.eml", name)
+    }
+}
+
+/// A path in `dir` for `name` that does not overwrite an existing file:
+/// `report.pdf`, `report (1).pdf`, `report (2).pdf`, ...
+pub fn unique_path(dir: &Path, name: &str) -> PathBuf {
+    let name = sanitize(name);
+    let candidate = dir.join(&name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem, format!(".{}", ext)),
+        _ => (name.as_str(), String::new()),
+    };
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(|p| !p.exists())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Make an attachment name safe to use as a file name
+fn sanitize(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.trim_start_matches('.').to_string();
+    if cleaned.is_empty() {
+        "attachment".to_string()
+    } else {
+        cleaned
+    }
+}
@@ -0,0 +1,218 @@
+//! Free/busy lookup (`getSchedule`) and meeting time suggestions
+//! (`findMeetingTimes`)
+//!
+//! Ranges are given and shown in the mailbox timezone.
+
+use chrono::{Duration, NaiveTime};
+use clap::Args;
+use fafafa_outlook_core::{DateTimeTimeZone, MeetingTimeRequest, OutlookClient};
+
+use crate::agenda::format_duration;
+use crate::dates::{self, Clock};
+use crate::settings;
+
+#[derive(Args)]
+pub struct FreeBusyArgs {
+    /// Email addresses to look up
+    #[arg(required = true)]
+    emails: Vec<String>,
+    /// Start of the range (e.g. 'tomorrow 9:00'; default: today)
+    #[arg(long)]
+    start: Option<String>,
+    /// End of the range (default: one day after the start)
+    #[arg(long)]
+    end: Option<String>,
+    /// Granularity of the availability view in minutes
+    #[arg(long, default_value = "30")]
+    interval: u32,
+}
+
+#[derive(Args)]
+pub struct FindTimeArgs {
+    /// Attendee email addresses
+    #[arg(required = true)]
+    emails: Vec<String>,
+    /// Meeting length (e.g. 30m, 1h)
+    #[arg(short, long, default_value = "30m")]
+    duration: String,
+    /// When to look (e.g. 'next week', 'this week', tomorrow, 'next 3 days')
+    #[arg(short, long, default_value = "next 7 days")]
+    within: String,
+    /// Maximum number of suggestions
+    #[arg(short, long, default_value = "5")]
+    limit: u32,
+    /// Consider any time of day, not only working hours
+    #[arg(long)]
+    any_time: bool,
+}
+
+pub async fn freebusy(client: &OutlookClient, args: FreeBusyArgs) -> anyhow::Result<()> {
+    let timezone = settings::mailbox_timezone(client).await?;
+    let clock = Clock::new(&timezone)?;
+    let start = match &args.start {
+        Some(s) => clock.parse(s)?,
+        None => clock.today().and_time(NaiveTime::MIN),
+    };
+    let end = match &args.end {
+        Some(e) => clock.parse(e)?,
+        None => start + Duration::days(1),
+    };
+    if end <= start {
+        anyhow::bail!("end must be after start");
+    }
+
+    let emails: Vec<&str> = args.emails.iter().map(|s| s.as_str()).collect();
+    let schedules = client
+        .get_schedule(
+            &emails,
+            DateTimeTimeZone::new(&dates::format(start), &timezone),
+            DateTimeTimeZone::new(&dates::format(end), &timezone),
+            args.interval,
+        )
+        .await?;
+
+    println!(
+        "Free/busy {} - {} ({})",
+        start.format("%Y-%m-%d %H:%M"),
+        end.format("%Y-%m-%d %H:%M"),
+        timezone
+    );
+    for schedule in schedules {
+        println!();
+        println!("{}", schedule.schedule_id);
+        if let Some(error) = &schedule.error {
+            println!("  unavailable: {}", error.message);
+            continue;
+        }
+        let mut busy: Vec<_> = schedule
+            .schedule_items
+            .iter()
+            .filter(|item| item.status != "free")
+            .filter_map(|item| {
+                let from = clock.convert(&item.start)?;
+                let to = clock.convert(&item.end)?;
+                Some((from, to, item))
+            })
+            .collect();
+        busy.sort_by_key(|(from, _, _)| *from);
+        if busy.is_empty() {
+            println!("  free for the whole range");
+            continue;
+        }
+        for (from, to, item) in busy {
+            let detail = item
+                .subject
+                .as_deref()
+                .filter(|s| !s.is_empty())
+                .map(|s| format!("  {}", s))
+                .unwrap_or_default();
+            println!(
+                "  {} - {}  {}{}",
+                from.format("%a %m-%d %H:%M"),
+                if to.date() == from.date() {
+                    to.format("%H:%M").to_string()
+                } else {
+                    to.format("%a %m-%d %H:%M").to_string()
+                },
+                describe_status(&item.status),
+                detail
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn find_time(client: &OutlookClient, args: FindTimeArgs) -> anyhow::Result<()> {
+    let timezone = settings::mailbox_timezone(client).await?;
+    let clock = Clock::new(&timezone)?;
+    let duration = dates::parse_duration(&args.duration)?;
+    let (start, end) = clock.parse_range(&args.within)?;
+
+    let emails: Vec<&str> = args.emails.iter().map(|s| s.as_str()).collect();
+    let request = MeetingTimeRequest::new(&emails, duration.num_minutes() as u32)
+        .time_window(
+            DateTimeTimeZone::new(&dates::format(start), &timezone),
+            DateTimeTimeZone::new(&dates::format(end), &timezone),
+        )
+        .max_candidates(args.limit)
+        .activity_domain(if args.any_time {
+            "unrestricted"
+        } else {
+            "work"
+        });
+    let result = client.find_meeting_times(request).await?;
+
+    let mut suggestions: Vec<_> = result
+        .meeting_time_suggestions
+        .iter()
+        .filter_map(|s| {
+            let from = clock.convert(&s.meeting_time_slot.start)?;
+            let to = clock.convert(&s.meeting_time_slot.end)?;
+            Some((from, to, s))
+        })
+        .collect();
+    if suggestions.is_empty() {
+        let reason = result
+            .empty_suggestions_reason
+            .as_deref()
+            .filter(|r| !r.is_empty())
+            .map(|r| format!(" ({})", r))
+            .unwrap_or_default();
+        println!("No free slot found{}", reason);
+        return Ok(());
+    }
+    // Best first: highest confidence, then earliest
+    suggestions.sort_by(|a, b| {
+        let (ca, cb) = (a.2.confidence.unwrap_or(0.0), b.2.confidence.unwrap_or(0.0));
+        cb.total_cmp(&ca).then(a.0.cmp(&b.0))
+    });
+
+    println!(
+        "Candidate times for {} ({}):",
+        format_duration(duration),
+        timezone
+    );
+    for (rank, (from, to, suggestion)) in suggestions.iter().enumerate() {
+        let confidence = suggestion
+            .confidence
+            .map(|c| format!("  {:.0}%", c))
+            .unwrap_or_default();
+        println!(
+            "  {}. {} - {}{}",
+            rank + 1,
+            from.format("%a %Y-%m-%d %H:%M"),
+            to.format("%H:%M"),
+            confidence
+        );
+        let conflicts: Vec<String> = suggestion
+            .attendee_availability
+            .iter()
+            .filter(|a| a.availability != "free")
+            .map(|a| {
+                format!(
+                    "{} {}",
+                    a.attendee.email_address.address,
+                    describe_status(&a.availability)
+                )
+            })
+            .collect();
+        if conflicts.is_empty() {
+            println!("     everyone free");
+        } else {
+            println!("     {}", conflicts.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// Graph freeBusyStatus -> display text
+fn describe_status(status: &str) -> &str {
+    match status {
+        "busy" => "busy",
+        "tentative" => "tentative",
+        "oof" => "out of office",
+        "workingElsewhere" => "working elsewhere",
+        "free" => "free",
+        _ => "unknown",
+    }
+}
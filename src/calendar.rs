@@ -0,0 +1,544 @@
+//! Calendar event commands beyond simple create/delete
+
+use chrono::{Datelike, Duration, NaiveDate};
+use clap::{Args, Subcommand, ValueEnum};
+use fafafa_outlook_core::{
+    Attendee, CalendarEvent, DateTimeTimeZone, EmailAddress, EventResponse, EventUpdate,
+    OutlookClient, PatternedRecurrence, RecurrencePattern, RecurrenceRange,
+};
+
+use crate::dates::{self, Clock};
+use crate::settings;
+
+#[derive(Args)]
+pub struct UpdateEventArgs {
+    /// Event ID
+    id: String,
+    /// New subject
+    #[arg(short, long)]
+    subject: Option<String>,
+    /// New start (ISO 8601 or e.g. 'tomorrow 10:00'); keeps the current length without --end/--duration
+    #[arg(long)]
+    start: Option<String>,
+    /// New end (ISO 8601 or e.g. 'tomorrow 11:00')
+    #[arg(long, conflicts_with = "duration")]
+    end: Option<String>,
+    /// New duration from the start instead of --end (e.g. 45m, 1h30m)
+    #[arg(long, requires = "start")]
+    duration: Option<String>,
+    /// Timezone for --start/--end (default: the event's original timezone, else the mailbox timezone)
+    #[arg(long)]
+    timezone: Option<String>,
+    /// New location
+    #[arg(short, long)]
+    location: Option<String>,
+    /// Attendees to add (comma-separated emails)
+    #[arg(long)]
+    add_attendees: Option<String>,
+    /// Attendees to remove (comma-separated emails)
+    #[arg(long)]
+    remove_attendees: Option<String>,
+    /// New body
+    #[arg(short, long)]
+    body: Option<String>,
+    /// Body is HTML
+    #[arg(long, requires = "body")]
+    html: bool,
+    /// Make it an online meeting
+    #[arg(long, conflicts_with = "no_online")]
+    online: bool,
+    /// Remove the online meeting
+    #[arg(long)]
+    no_online: bool,
+}
+
+/// Recurrence options for `create-event`
+#[derive(Args)]
+pub struct RecurrenceArgs {
+    /// Repeat the event
+    #[arg(long, value_enum)]
+    repeat: Option<Repeat>,
+    /// Repeat every N days/weeks/months/years
+    #[arg(long, default_value = "1", requires = "repeat")]
+    interval: u32,
+    /// Days for weekly repeats (comma-separated, e.g. mon,wed; default: the start day)
+    #[arg(long, requires = "repeat")]
+    days: Option<String>,
+    /// Last date of the series (YYYY-MM-DD)
+    #[arg(long, requires = "repeat", conflicts_with = "count")]
+    until: Option<String>,
+    /// Number of occurrences
+    #[arg(long, requires = "repeat")]
+    count: Option<u32>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Repeat {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl RecurrenceArgs {
+    /// Build a Graph patternedRecurrence anchored on the event's start
+    pub fn build(
+        &self,
+        start: &str,
+        timezone: &str,
+    ) -> anyhow::Result<Option<PatternedRecurrence>> {
+        let Some(repeat) = self.repeat else {
+            return Ok(None);
+        };
+        if self.days.is_some() && !matches!(repeat, Repeat::Weekly) {
+            anyhow::bail!("--days only applies to --repeat weekly");
+        }
+        let start_date = start
+            .get(..10)
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .ok_or_else(|| anyhow::anyhow!("cannot read a date from start '{}'", start))?;
+
+        if self.interval == 0 {
+            anyhow::bail!("--interval must be at least 1");
+        }
+        if self.count == Some(0) {
+            anyhow::bail!("--count must be at least 1");
+        }
+
+        let mut pattern = RecurrencePattern {
+            interval: self.interval as i32,
+            ..Default::default()
+        };
+        match repeat {
+            Repeat::Daily => pattern.pattern_type = "daily".to_string(),
+            Repeat::Weekly => {
+                pattern.pattern_type = "weekly".to_string();
+                pattern.days_of_week = Some(match &self.days {
+                    Some(days) => settings::parse_days(days)?,
+                    None => vec![weekday_name(start_date.weekday()).to_string()],
+                });
+            }
+            Repeat::Monthly => {
+                pattern.pattern_type = "absoluteMonthly".to_string();
+                pattern.day_of_month = Some(start_date.day() as i32);
+            }
+            Repeat::Yearly => {
+                pattern.pattern_type = "absoluteYearly".to_string();
+                pattern.day_of_month = Some(start_date.day() as i32);
+                pattern.month = Some(start_date.month() as i32);
+            }
+        }
+
+        let mut range = RecurrenceRange {
+            range_type: "noEnd".to_string(),
+            start_date: start_date.format("%Y-%m-%d").to_string(),
+            recurrence_time_zone: Some(timezone.to_string()),
+            ..Default::default()
+        };
+        if let Some(until) = &self.until {
+            let end = NaiveDate::parse_from_str(until, "%Y-%m-%d").map_err(|_| {
+                anyhow::anyhow!("invalid --until '{}' (expected YYYY-MM-DD)", until)
+            })?;
+            if end < start_date {
+                anyhow::bail!("--until {} is before the start date", until);
+            }
+            range.range_type = "endDate".to_string();
+            range.end_date = Some(end.format("%Y-%m-%d").to_string());
+        } else if let Some(count) = self.count {
+            range.range_type = "numbered".to_string();
+            range.number_of_occurrences = Some(count as i32);
+        }
+
+        Ok(Some(PatternedRecurrence { pattern, range }))
+    }
+}
+
+fn weekday_name(day: chrono::Weekday) -> &'static str {
+    match day {
+        chrono::Weekday::Mon => "monday",
+        chrono::Weekday::Tue => "tuesday",
+        chrono::Weekday::Wed => "wednesday",
+        chrono::Weekday::Thu => "thursday",
+        chrono::Weekday::Fri => "friday",
+        chrono::Weekday::Sat => "saturday",
+        chrono::Weekday::Sun => "sunday",
+    }
+}
+
+/// Human-readable summary, e.g. "every 2 weeks on monday, wednesday until 2024-06-30"
+pub fn describe_recurrence(recurrence: &PatternedRecurrence) -> String {
+    let pattern = &recurrence.pattern;
+    let unit = match pattern.pattern_type.as_str() {
+        "daily" => "day",
+        "weekly" => "week",
+        "absoluteMonthly" | "relativeMonthly" => "month",
+        "absoluteYearly" | "relativeYearly" => "year",
+        other => other,
+    };
+    let mut text = if pattern.interval > 1 {
+        format!("every {} {}s", pattern.interval, unit)
+    } else {
+        format!("every {}", unit)
+    };
+    if let Some(days) = pattern.days_of_week.as_ref().filter(|d| !d.is_empty()) {
+        text.push_str(&format!(" on {}", days.join(", ")));
+    }
+    match pattern.pattern_type.as_str() {
+        "absoluteMonthly" => {
+            if let Some(day) = pattern.day_of_month {
+                text.push_str(&format!(" on day {}", day));
+            }
+        }
+        "absoluteYearly" => {
+            if let (Some(day), Some(month)) = (pattern.day_of_month, pattern.month) {
+                text.push_str(&format!(" on {:02}-{:02}", month, day));
+            }
+        }
+        _ => {}
+    }
+
+    let range = &recurrence.range;
+    text.push_str(&format!(" from {}", range.start_date));
+    match range.range_type.as_str() {
+        "endDate" => {
+            if let Some(end) = &range.end_date {
+                text.push_str(&format!(" until {}", end));
+            }
+        }
+        "numbered" => {
+            if let Some(n) = range.number_of_occurrences {
+                text.push_str(&format!(", {} times", n));
+            }
+        }
+        _ => {}
+    }
+    text
+}
+
+/// Default time window for calendar views
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Window {
+    /// From local midnight today to midnight tomorrow
+    Today,
+    /// From Monday of the current week to the next Monday
+    Week,
+}
+
+impl Window {
+    /// Start and end as UTC ISO 8601 strings for a calendar view query
+    pub fn range(self, clock: &Clock) -> (String, String) {
+        let today = clock.today();
+        let (start, days) = match self {
+            Window::Today => (today, 1),
+            Window::Week => (dates::start_of_week(today), 7),
+        };
+        (
+            midnight_utc(clock, start),
+            midnight_utc(clock, start + Duration::days(days)),
+        )
+    }
+}
+
+fn midnight_utc(clock: &Clock, date: NaiveDate) -> String {
+    dates::format_utc(clock.to_utc(date.and_time(chrono::NaiveTime::MIN)))
+}
+
+/// Calendar view bounds from --start/--end (either may be omitted) or a
+/// window, as UTC strings
+pub fn view_range(
+    clock: &Clock,
+    start: Option<&str>,
+    end: Option<&str>,
+    window: Window,
+) -> anyhow::Result<(String, String)> {
+    if start.is_none() && end.is_none() {
+        return Ok(window.range(clock));
+    }
+    let start = match start {
+        Some(s) => clock.parse(s)?,
+        None => clock.today().and_time(chrono::NaiveTime::MIN),
+    };
+    let end = match end {
+        Some(e) => clock.parse(e)?,
+        None => start + Duration::weeks(1),
+    };
+    if end <= start {
+        anyhow::bail!("end must be after start");
+    }
+    Ok((
+        dates::format_utc(clock.to_utc(start)),
+        dates::format_utc(clock.to_utc(end)),
+    ))
+}
+
+/// Print events one per line; occurrences of a series are marked
+pub fn print_events(events: &[CalendarEvent]) {
+    for event in events {
+        let start_str = event
+            .start
+            .as_ref()
+            .map(|d| d.date_time.as_str())
+            .unwrap_or("?");
+        let subject = event.subject.as_deref().unwrap_or("(no subject)");
+        let kind = match event.event_type.as_deref() {
+            Some("occurrence") => " [occurrence]",
+            Some("exception") => " [modified occurrence]",
+            Some("seriesMaster") => " [series]",
+            _ => "",
+        };
+        println!("  {} - {}{}", start_str, subject, kind);
+        println!("    ID: {}", event.id);
+        if let Some(series) = &event.series_master_id {
+            println!("    Series: {}", series);
+        }
+    }
+}
+
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct InstancesArgs {
+    /// Series master event ID
+    #[arg(required = true)]
+    series_id: Option<String>,
+    /// Start of the range (e.g. 2024-01-01, 'next monday'; default: today)
+    #[arg(long)]
+    start: Option<String>,
+    /// End of the range (default: 12 weeks after the start)
+    #[arg(long)]
+    end: Option<String>,
+    /// Number of occurrences to show
+    #[arg(short, long, default_value = "20")]
+    limit: u32,
+    #[command(subcommand)]
+    command: Option<InstancesCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum InstancesCommand {
+    /// Edit a single occurrence (same options as update-event)
+    Edit(UpdateEventArgs),
+    /// Cancel a single occurrence and notify attendees
+    Cancel {
+        /// Occurrence ID
+        id: String,
+        /// Message to attendees
+        #[arg(short, long)]
+        comment: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EventsCommand {
+    /// Export events (iCalendar)
+    Export(crate::ics::ExportArgs),
+}
+
+/// Weeks of occurrences listed when `instances` gets no --end
+const DEFAULT_INSTANCE_WEEKS: i64 = 12;
+
+pub async fn instances(client: &OutlookClient, args: InstancesArgs) -> anyhow::Result<()> {
+    match args.command {
+        Some(InstancesCommand::Edit(update)) => update_event(client, update).await?,
+        Some(InstancesCommand::Cancel { id, comment }) => {
+            client.cancel_event(&id, comment.as_deref()).await?;
+            println!("Occurrence cancelled: {}", id);
+        }
+        None => {
+            let series_id = args
+                .series_id
+                .ok_or_else(|| anyhow::anyhow!("missing series ID"))?;
+            let clock = Clock::new(&settings::mailbox_timezone(client).await?)?;
+            let start = match &args.start {
+                Some(s) => clock.parse(s)?,
+                None => clock.today().and_time(chrono::NaiveTime::MIN),
+            };
+            let end = match &args.end {
+                Some(e) => clock.parse(e)?,
+                None => start + Duration::weeks(DEFAULT_INSTANCE_WEEKS),
+            };
+            let start = dates::format_utc(clock.to_utc(start));
+            let end = dates::format_utc(clock.to_utc(end));
+            let events = client
+                .list_event_instances(&series_id, &start, &end, args.limit)
+                .await?;
+            if events.is_empty() {
+                println!("No occurrences between {} and {}", start, end);
+            } else {
+                println!("Occurrences:");
+                print_events(&events);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Counter-proposal for decline/tentative responses
+#[derive(Args)]
+pub struct ProposeTime {
+    /// Propose a new start time to the organizer (ISO 8601 or e.g. 'tomorrow 14:00')
+    #[arg(long, requires = "propose_end", conflicts_with = "no_send_response")]
+    propose_start: Option<String>,
+    /// Propose a new end time to the organizer
+    #[arg(long, requires = "propose_start")]
+    propose_end: Option<String>,
+    /// Timezone for the proposed times (default: mailbox timezone)
+    #[arg(long)]
+    timezone: Option<String>,
+}
+
+/// Send an accept/tentative/decline response, with an optional comment and
+/// proposed new time
+pub async fn respond(
+    client: &OutlookClient,
+    id: &str,
+    mut response: EventResponse,
+    comment: Option<String>,
+    propose: Option<ProposeTime>,
+) -> anyhow::Result<()> {
+    if let Some(comment) = comment {
+        response = response.comment(comment);
+    }
+    if let Some(ProposeTime {
+        propose_start: Some(start),
+        propose_end: Some(end),
+        timezone,
+    }) = propose
+    {
+        let timezone = match timezone {
+            Some(tz) => tz,
+            None => settings::mailbox_timezone(client).await?,
+        };
+        let clock = Clock::new(&timezone)?;
+        response = response.propose_new_time(
+            DateTimeTimeZone::new(&dates::format(clock.parse(&start)?), &timezone),
+            DateTimeTimeZone::new(&dates::format(clock.parse(&end)?), &timezone),
+        );
+    }
+    client.respond_to_event(id, response).await?;
+    Ok(())
+}
+
+fn split_emails(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// PATCH only the fields given on the command line
+pub async fn update_event(client: &OutlookClient, args: UpdateEventArgs) -> anyhow::Result<()> {
+    let needs_current = args.add_attendees.is_some()
+        || args.remove_attendees.is_some()
+        || args.start.is_some()
+        || args.end.is_some();
+    let current = if needs_current {
+        Some(client.get_event(&args.id).await?)
+    } else {
+        None
+    };
+
+    let mut update = EventUpdate::new();
+    if let Some(subject) = args.subject {
+        update = update.subject(subject);
+    }
+    if args.start.is_some() || args.end.is_some() {
+        let timezone = match &args.timezone {
+            Some(tz) => tz.clone(),
+            // The zone the organizer created the event in; `start` comes
+            // back in UTC
+            None => match current
+                .as_ref()
+                .and_then(|e| e.original_start_time_zone.clone())
+                .filter(|tz| !tz.is_empty() && tz != "tzone://Microsoft/Custom")
+            {
+                Some(tz) => tz,
+                None => settings::mailbox_timezone(client).await?,
+            },
+        };
+        let clock = Clock::new(&timezone)?;
+        let current_start = current
+            .as_ref()
+            .and_then(|e| e.start.as_ref())
+            .and_then(|dt| clock.convert(dt));
+        let current_end = current
+            .as_ref()
+            .and_then(|e| e.end.as_ref())
+            .and_then(|dt| clock.convert(dt));
+        let start = args.start.as_deref().map(|s| clock.parse(s)).transpose()?;
+        let end = match (&args.end, &args.duration, start) {
+            (Some(end), _, _) => Some(clock.parse(end)?),
+            (None, Some(duration), Some(start)) => Some(start + dates::parse_duration(duration)?),
+            // Moving the start keeps the current length
+            (None, None, Some(start)) => match (current_start, current_end) {
+                (Some(from), Some(to)) => Some(start + (to - from)),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let (Some(start), Some(end)) = (start.or(current_start), end.or(current_end)) {
+            if end <= start {
+                anyhow::bail!("end must be after start");
+            }
+        }
+        if let Some(start) = start {
+            update = update.start(DateTimeTimeZone::new(&dates::format(start), &timezone));
+        }
+        if let Some(end) = end {
+            update = update.end(DateTimeTimeZone::new(&dates::format(end), &timezone));
+        }
+    }
+    if let Some(location) = args.location {
+        update = update.location(location);
+    }
+    if let Some(event) = &current {
+        if args.add_attendees.is_some() || args.remove_attendees.is_some() {
+            // Keep existing attendees as they are (type, display name)
+            let mut attendees: Vec<Attendee> = event.attendees.clone().unwrap_or_default();
+            if let Some(remove) = &args.remove_attendees {
+                let remove = split_emails(remove);
+                attendees.retain(|a| {
+                    !remove
+                        .iter()
+                        .any(|r| r.eq_ignore_ascii_case(&a.email_address.address))
+                });
+            }
+            if let Some(add) = &args.add_attendees {
+                for email in split_emails(add) {
+                    let present = attendees
+                        .iter()
+                        .any(|a| a.email_address.address.eq_ignore_ascii_case(&email));
+                    if !present {
+                        attendees.push(Attendee {
+                            email_address: EmailAddress {
+                                address: email,
+                                name: None,
+                            },
+                            attendee_type: Some("required".to_string()),
+                            status: None,
+                        });
+                    }
+                }
+            }
+            update = update.attendee_list(attendees);
+        }
+    }
+    if let Some(body) = args.body {
+        update = if args.html {
+            update.body_html(body)
+        } else {
+            update.body_text(body)
+        };
+    }
+    if args.online {
+        update = update.online_meeting(true);
+    } else if args.no_online {
+        update = update.online_meeting(false);
+    }
+
+    if update.is_empty() {
+        anyhow::bail!("nothing to update (see `update-event --help`)");
+    }
+    client.update_event(&args.id, update).await?;
+    println!("Event updated: {}", args.id);
+    Ok(())
+}
@@ -0,0 +1,168 @@
+//! Calendar management and `--calendar` resolution
+//!
+//! `--calendar` takes a calendar ID or name. Names are matched against the
+//! user's own calendars and calendars shared with them, then against the
+//! Microsoft 365 groups they belong to (by group name or address). A group
+//! calendar can also be given explicitly as `group:<group id>`.
+
+use clap::Subcommand;
+use fafafa_outlook_core::{Calendar, CalendarRef, Group, OutlookClient};
+
+const GROUP_PREFIX: &str = "group:";
+
+#[derive(Subcommand)]
+pub enum CalendarsCommand {
+    /// List own, shared and group calendars
+    List,
+    /// Create a calendar
+    Create {
+        /// Calendar name
+        name: String,
+    },
+    /// Delete a calendar and all its events
+    Delete {
+        /// Calendar name or ID
+        calendar: String,
+    },
+}
+
+pub async fn run(client: &OutlookClient, command: CalendarsCommand) -> anyhow::Result<()> {
+    match command {
+        CalendarsCommand::List => {
+            let calendars = client.list_calendars().await?;
+            let me = own_address(&calendars);
+            println!("Calendars:");
+            for cal in &calendars {
+                let mut notes = Vec::new();
+                if cal.is_default_calendar.unwrap_or(false) {
+                    notes.push("default".to_string());
+                }
+                if let Some(owner) = shared_owner(cal, me) {
+                    notes.push(format!("shared by {}", owner));
+                }
+                if !cal.can_edit.unwrap_or(true) {
+                    notes.push("read-only".to_string());
+                }
+                let notes = if notes.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", notes.join(", "))
+                };
+                println!("  {}{}", cal.name, notes);
+                println!("    ID: {}", cal.id);
+            }
+            let groups = list_groups(client).await;
+            if !groups.is_empty() {
+                println!("Group calendars:");
+                for group in groups {
+                    match group.mail.as_deref() {
+                        Some(mail) => println!("  {} <{}>", group.display_name, mail),
+                        None => println!("  {}", group.display_name),
+                    }
+                    println!("    ID: {}{}", GROUP_PREFIX, group.id);
+                }
+            }
+        }
+        CalendarsCommand::Create { name } => {
+            let cal = client.create_calendar(&name).await?;
+            println!("Calendar created: {}", cal.name);
+            println!("  ID: {}", cal.id);
+        }
+        CalendarsCommand::Delete { calendar } => {
+            let calendars = client.list_calendars().await?;
+            let cal = match_calendar(&calendars, &calendar)?.ok_or_else(|| {
+                anyhow::anyhow!("no calendar named '{}' (see `calendars list`)", calendar)
+            })?;
+            if cal.is_default_calendar.unwrap_or(false) {
+                anyhow::bail!("the default calendar cannot be deleted");
+            }
+            if shared_owner(&cal, own_address(&calendars)).is_some() {
+                anyhow::bail!(
+                    "'{}' is shared with you; only its owner can delete it",
+                    cal.name
+                );
+            }
+            client.delete_calendar(&cal.id).await?;
+            println!("Calendar deleted: {}", cal.name);
+        }
+    }
+    Ok(())
+}
+
+/// Resolve `--calendar`; no value means the default calendar
+pub async fn resolve(
+    client: &OutlookClient,
+    calendar: Option<&str>,
+) -> anyhow::Result<CalendarRef> {
+    let Some(calendar) = calendar else {
+        return Ok(CalendarRef::Default);
+    };
+    if let Some(group_id) = calendar.strip_prefix(GROUP_PREFIX) {
+        return Ok(CalendarRef::Group(group_id.to_string()));
+    }
+    if let Some(cal) = match_calendar(&client.list_calendars().await?, calendar)? {
+        return Ok(CalendarRef::Calendar(cal.id));
+    }
+    let groups = list_groups(client).await;
+    let matches: Vec<_> = groups
+        .iter()
+        .filter(|g| {
+            g.id == calendar
+                || g.display_name.eq_ignore_ascii_case(calendar)
+                || g.mail
+                    .as_deref()
+                    .is_some_and(|m| m.eq_ignore_ascii_case(calendar))
+        })
+        .collect();
+    match matches.as_slice() {
+        [group] => Ok(CalendarRef::Group(group.id.clone())),
+        [] => anyhow::bail!("no calendar named '{}' (see `calendars list`)", calendar),
+        _ => anyhow::bail!(
+            "'{}' matches several groups; use group:<id> (see `calendars list`)",
+            calendar
+        ),
+    }
+}
+
+/// The user's groups; a failed lookup (e.g. no `Group.Read.All` consent)
+/// only hides group calendars
+async fn list_groups(client: &OutlookClient) -> Vec<Group> {
+    client.list_groups().await.unwrap_or_else(|e| {
+        eprintln!("Warning: cannot list group calendars: {}", e);
+        Vec::new()
+    })
+}
+
+fn match_calendar(calendars: &[Calendar], calendar: &str) -> anyhow::Result<Option<Calendar>> {
+    if let Some(cal) = calendars.iter().find(|c| c.id == calendar) {
+        return Ok(Some(cal.clone()));
+    }
+    let matches: Vec<&Calendar> = calendars
+        .iter()
+        .filter(|c| c.name.eq_ignore_ascii_case(calendar))
+        .collect();
+    match matches.as_slice() {
+        [] => Ok(None),
+        [cal] => Ok(Some((*cal).clone())),
+        _ => anyhow::bail!(
+            "'{}' matches several calendars; use its ID (see `calendars list`)",
+            calendar
+        ),
+    }
+}
+
+/// The user's own address: the owner of their default calendar
+fn own_address(calendars: &[Calendar]) -> Option<&str> {
+    calendars
+        .iter()
+        .find(|c| c.is_default_calendar.unwrap_or(false))
+        .and_then(|c| c.owner.as_ref())
+        .map(|o| o.address.as_str())
+}
+
+/// Owner address of a calendar shared with the user; `None` for own calendars
+fn shared_owner<'a>(cal: &'a Calendar, me: Option<&str>) -> Option<&'a str> {
+    let owner = cal.owner.as_ref()?.address.as_str();
+    let own = me.is_some_and(|me| me.eq_ignore_ascii_case(owner));
+    (!owner.is_empty() && !own).then_some(owner)
+}
@@ -0,0 +1,618 @@
+//! Date and time input for calendar commands
+//!
+//! Besides ISO 8601, `--start`/`--end` style options accept relative forms
+//! such as `tomorrow 10:00`, `next monday 9am`, `friday 15:30` and `+2h`.
+//! They are resolved as wall-clock times in the mailbox timezone, which may
+//! be a Windows name (`China Standard Time`) or an IANA name
+//! (`Asia/Shanghai`).
+
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike,
+    Utc, Weekday,
+};
+use chrono_tz::Tz;
+use fafafa_outlook_core::DateTimeTimeZone;
+
+/// Wall-clock format Graph expects in `dateTimeTimeZone`
+const GRAPH_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// Windows timezone names mapped to IANA: CLDR `windowsZones.xml`
+/// (territory `001`), plus the legacy names Exchange may still report
+const WINDOWS_ZONES: &[(&str, &str)] = &[
+    ("Dateline Standard Time", "Etc/GMT+12"),
+    ("UTC-11", "Etc/GMT+11"),
+    ("Aleutian Standard Time", "America/Adak"),
+    ("Hawaiian Standard Time", "Pacific/Honolulu"),
+    ("Marquesas Standard Time", "Pacific/Marquesas"),
+    ("Alaskan Standard Time", "America/Anchorage"),
+    ("UTC-09", "Etc/GMT+9"),
+    ("Pacific Standard Time (Mexico)", "America/Tijuana"),
+    ("UTC-08", "Etc/GMT+8"),
+    ("Pacific Standard Time", "America/Los_Angeles"),
+    ("US Mountain Standard Time", "America/Phoenix"),
+    ("Mountain Standard Time (Mexico)", "America/Mazatlan"),
+    ("Mountain Standard Time", "America/Denver"),
+    ("Yukon Standard Time", "America/Whitehorse"),
+    ("Central America Standard Time", "America/Guatemala"),
+    ("Central Standard Time", "America/Chicago"),
+    ("Easter Island Standard Time", "Pacific/Easter"),
+    ("Central Standard Time (Mexico)", "America/Mexico_City"),
+    ("Canada Central Standard Time", "America/Regina"),
+    ("SA Pacific Standard Time", "America/Bogota"),
+    ("Eastern Standard Time (Mexico)", "America/Cancun"),
+    ("Eastern Standard Time", "America/New_York"),
+    ("Haiti Standard Time", "America/Port-au-Prince"),
+    ("Cuba Standard Time", "America/Havana"),
+    ("US Eastern Standard Time", "America/Indianapolis"),
+    ("Turks And Caicos Standard Time", "America/Grand_Turk"),
+    ("Paraguay Standard Time", "America/Asuncion"),
+    ("Atlantic Standard Time", "America/Halifax"),
+    ("Venezuela Standard Time", "America/Caracas"),
+    ("Central Brazilian Standard Time", "America/Cuiaba"),
+    ("SA Western Standard Time", "America/La_Paz"),
+    ("Pacific SA Standard Time", "America/Santiago"),
+    ("Newfoundland Standard Time", "America/St_Johns"),
+    ("Tocantins Standard Time", "America/Araguaina"),
+    ("E. South America Standard Time", "America/Sao_Paulo"),
+    ("SA Eastern Standard Time", "America/Cayenne"),
+    ("Argentina Standard Time", "America/Buenos_Aires"),
+    ("Greenland Standard Time", "America/Godthab"),
+    ("Montevideo Standard Time", "America/Montevideo"),
+    ("Magallanes Standard Time", "America/Punta_Arenas"),
+    ("Saint Pierre Standard Time", "America/Miquelon"),
+    ("Bahia Standard Time", "America/Bahia"),
+    ("UTC-02", "Etc/GMT+2"),
+    ("Mid-Atlantic Standard Time", "Etc/GMT+2"),
+    ("Azores Standard Time", "Atlantic/Azores"),
+    ("Cape Verde Standard Time", "Atlantic/Cape_Verde"),
+    ("UTC", "Etc/UTC"),
+    ("Coordinated Universal Time", "Etc/UTC"),
+    ("GMT Standard Time", "Europe/London"),
+    ("Greenwich Standard Time", "Atlantic/Reykjavik"),
+    ("Sao Tome Standard Time", "Africa/Sao_Tome"),
+    ("Morocco Standard Time", "Africa/Casablanca"),
+    ("W. Europe Standard Time", "Europe/Berlin"),
+    ("Central Europe Standard Time", "Europe/Budapest"),
+    ("Romance Standard Time", "Europe/Paris"),
+    ("Central European Standard Time", "Europe/Warsaw"),
+    ("W. Central Africa Standard Time", "Africa/Lagos"),
+    ("Jordan Standard Time", "Asia/Amman"),
+    ("GTB Standard Time", "Europe/Bucharest"),
+    ("Middle East Standard Time", "Asia/Beirut"),
+    ("Egypt Standard Time", "Africa/Cairo"),
+    ("E. Europe Standard Time", "Europe/Chisinau"),
+    ("Syria Standard Time", "Asia/Damascus"),
+    ("West Bank Standard Time", "Asia/Hebron"),
+    ("South Africa Standard Time", "Africa/Johannesburg"),
+    ("FLE Standard Time", "Europe/Kiev"),
+    ("Israel Standard Time", "Asia/Jerusalem"),
+    ("South Sudan Standard Time", "Africa/Juba"),
+    ("Kaliningrad Standard Time", "Europe/Kaliningrad"),
+    ("Sudan Standard Time", "Africa/Khartoum"),
+    ("Libya Standard Time", "Africa/Tripoli"),
+    ("Namibia Standard Time", "Africa/Windhoek"),
+    ("Arabic Standard Time", "Asia/Baghdad"),
+    ("Turkey Standard Time", "Europe/Istanbul"),
+    ("Arab Standard Time", "Asia/Riyadh"),
+    ("Belarus Standard Time", "Europe/Minsk"),
+    ("Russian Standard Time", "Europe/Moscow"),
+    ("E. Africa Standard Time", "Africa/Nairobi"),
+    ("Volgograd Standard Time", "Europe/Volgograd"),
+    ("Iran Standard Time", "Asia/Tehran"),
+    ("Arabian Standard Time", "Asia/Dubai"),
+    ("Astrakhan Standard Time", "Europe/Astrakhan"),
+    ("Azerbaijan Standard Time", "Asia/Baku"),
+    ("Russia Time Zone 3", "Europe/Samara"),
+    ("Mauritius Standard Time", "Indian/Mauritius"),
+    ("Saratov Standard Time", "Europe/Saratov"),
+    ("Georgian Standard Time", "Asia/Tbilisi"),
+    ("Caucasus Standard Time", "Asia/Yerevan"),
+    ("Afghanistan Standard Time", "Asia/Kabul"),
+    ("West Asia Standard Time", "Asia/Tashkent"),
+    ("Ekaterinburg Standard Time", "Asia/Yekaterinburg"),
+    ("Pakistan Standard Time", "Asia/Karachi"),
+    ("Qyzylorda Standard Time", "Asia/Qyzylorda"),
+    ("India Standard Time", "Asia/Calcutta"),
+    ("Sri Lanka Standard Time", "Asia/Colombo"),
+    ("Nepal Standard Time", "Asia/Katmandu"),
+    ("Central Asia Standard Time", "Asia/Almaty"),
+    ("Bangladesh Standard Time", "Asia/Dhaka"),
+    ("Omsk Standard Time", "Asia/Omsk"),
+    ("Myanmar Standard Time", "Asia/Rangoon"),
+    ("SE Asia Standard Time", "Asia/Bangkok"),
+    ("Altai Standard Time", "Asia/Barnaul"),
+    ("W. Mongolia Standard Time", "Asia/Hovd"),
+    ("North Asia Standard Time", "Asia/Krasnoyarsk"),
+    ("N. Central Asia Standard Time", "Asia/Novosibirsk"),
+    ("Tomsk Standard Time", "Asia/Tomsk"),
+    ("China Standard Time", "Asia/Shanghai"),
+    ("North Asia East Standard Time", "Asia/Irkutsk"),
+    ("Singapore Standard Time", "Asia/Singapore"),
+    ("W. Australia Standard Time", "Australia/Perth"),
+    ("Taipei Standard Time", "Asia/Taipei"),
+    ("Ulaanbaatar Standard Time", "Asia/Ulaanbaatar"),
+    ("Aus Central W. Standard Time", "Australia/Eucla"),
+    ("Transbaikal Standard Time", "Asia/Chita"),
+    ("Tokyo Standard Time", "Asia/Tokyo"),
+    ("North Korea Standard Time", "Asia/Pyongyang"),
+    ("Korea Standard Time", "Asia/Seoul"),
+    ("Yakutsk Standard Time", "Asia/Yakutsk"),
+    ("Cen. Australia Standard Time", "Australia/Adelaide"),
+    ("AUS Central Standard Time", "Australia/Darwin"),
+    ("E. Australia Standard Time", "Australia/Brisbane"),
+    ("AUS Eastern Standard Time", "Australia/Sydney"),
+    ("West Pacific Standard Time", "Pacific/Port_Moresby"),
+    ("Tasmania Standard Time", "Australia/Hobart"),
+    ("Vladivostok Standard Time", "Asia/Vladivostok"),
+    ("Lord Howe Standard Time", "Australia/Lord_Howe"),
+    ("Bougainville Standard Time", "Pacific/Bougainville"),
+    ("Russia Time Zone 10", "Asia/Srednekolymsk"),
+    ("Magadan Standard Time", "Asia/Magadan"),
+    ("Norfolk Standard Time", "Pacific/Norfolk"),
+    ("Sakhalin Standard Time", "Asia/Sakhalin"),
+    ("Central Pacific Standard Time", "Pacific/Guadalcanal"),
+    ("Russia Time Zone 11", "Asia/Kamchatka"),
+    ("Kamchatka Standard Time", "Asia/Kamchatka"),
+    ("New Zealand Standard Time", "Pacific/Auckland"),
+    ("UTC+12", "Etc/GMT-12"),
+    ("Fiji Standard Time", "Pacific/Fiji"),
+    ("Chatham Islands Standard Time", "Pacific/Chatham"),
+    ("UTC+13", "Etc/GMT-13"),
+    ("Tonga Standard Time", "Pacific/Tongatapu"),
+    ("Samoa Standard Time", "Pacific/Apia"),
+    ("Line Islands Standard Time", "Pacific/Kiritimati"),
+];
+
+/// Resolve a Windows or IANA timezone name
+pub fn zone(timezone: &str) -> Option<Tz> {
+    let timezone = timezone.trim();
+    WINDOWS_ZONES
+        .iter()
+        .find(|(windows, _)| windows.eq_ignore_ascii_case(timezone))
+        .map(|(_, iana)| *iana)
+        .unwrap_or(timezone)
+        .parse()
+        .ok()
+}
+
+/// Resolves date/time input in a given timezone
+pub struct Clock {
+    /// `None` for the system timezone
+    tz: Option<Tz>,
+}
+
+impl Clock {
+    /// A clock in `timezone`; unknown names are an error
+    pub fn new(timezone: &str) -> anyhow::Result<Self> {
+        let tz = zone(timezone).ok_or_else(|| {
+            anyhow::anyhow!(
+                "unknown timezone '{}' (use an IANA name such as Asia/Shanghai or a Windows name such as China Standard Time)",
+                timezone
+            )
+        })?;
+        Ok(Self { tz: Some(tz) })
+    }
+
+    /// A clock in the system timezone
+    pub fn local() -> Self {
+        Self { tz: None }
+    }
+
+    /// Current wall-clock time
+    pub fn now(&self) -> NaiveDateTime {
+        match self.tz {
+            Some(tz) => Utc::now().with_timezone(&tz).naive_local(),
+            None => Local::now().naive_local(),
+        }
+    }
+
+    pub fn today(&self) -> NaiveDate {
+        self.now().date()
+    }
+
+    /// Convert a wall-clock time to UTC (the earlier instant if ambiguous)
+    pub fn to_utc(&self, naive: NaiveDateTime) -> DateTime<Utc> {
+        let utc = match self.tz {
+            Some(tz) => tz
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc)),
+            None => Local
+                .from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc)),
+        };
+        // A time skipped by a DST jump: shift past the gap
+        utc.unwrap_or_else(|| self.to_utc(naive + Duration::hours(1)))
+    }
+
+    /// Convert a UTC instant to wall-clock time
+    pub fn wall_clock(&self, utc: DateTime<Utc>) -> NaiveDateTime {
+        match self.tz {
+            Some(tz) => utc.with_timezone(&tz).naive_local(),
+            None => utc.with_timezone(&Local).naive_local(),
+        }
+    }
+
+    /// Convert a Graph date/time in its own timezone to wall-clock time
+    pub fn convert(&self, dt: &DateTimeTimeZone) -> Option<NaiveDateTime> {
+        let naive = parse_graph(&dt.date_time)?;
+        let source = Clock::new(&dt.time_zone).ok()?;
+        Some(self.wall_clock(source.to_utc(naive)))
+    }
+
+    /// Parse absolute or relative input into a wall-clock time
+    pub fn parse(&self, input: &str) -> anyhow::Result<NaiveDateTime> {
+        self.parse_at(input, self.now())
+    }
+
+    /// [`Clock::parse`] relative to the wall-clock time `now`
+    fn parse_at(&self, input: &str, now: NaiveDateTime) -> anyhow::Result<NaiveDateTime> {
+        let s = input.trim().to_lowercase();
+        let invalid = || {
+            anyhow::anyhow!(
+                "invalid date/time '{}' (e.g. 2024-01-15T10:00, 'tomorrow 10:00', 'next monday 9am', +2h)",
+                input
+            )
+        };
+        if let Ok(dt) = DateTime::parse_from_rfc3339(input.trim()) {
+            return Ok(self.wall_clock(dt.with_timezone(&Utc)));
+        }
+        if let Some(dt) = parse_iso(&s) {
+            return Ok(dt);
+        }
+        if s == "now" {
+            return Ok(now);
+        }
+        if let Some(offset) = s.strip_prefix('+').or_else(|| s.strip_prefix("in ")) {
+            let at = now + parse_duration(offset).map_err(|_| invalid())?;
+            return Ok(at
+                .with_second(0)
+                .and_then(|t| t.with_nanosecond(0))
+                .unwrap_or(at));
+        }
+
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let (date, rest) = parse_day(&words, now.date()).ok_or_else(invalid)?;
+        let time = match rest {
+            [] => NaiveTime::MIN,
+            [time] => parse_time(time).ok_or_else(invalid)?,
+            // "10 am"
+            [time, suffix @ ("am" | "pm")] => {
+                parse_time(&format!("{}{}", time, suffix)).ok_or_else(invalid)?
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(date.and_time(time))
+    }
+
+    /// Parse a span such as `this week`, `next week`, `next 3 days`,
+    /// `tomorrow` or `next monday`. A single day covers the whole day; a
+    /// span never starts before now.
+    pub fn parse_range(&self, input: &str) -> anyhow::Result<(NaiveDateTime, NaiveDateTime)> {
+        self.parse_range_at(input, self.now())
+    }
+
+    /// [`Clock::parse_range`] relative to the wall-clock time `now`
+    fn parse_range_at(
+        &self,
+        input: &str,
+        now: NaiveDateTime,
+    ) -> anyhow::Result<(NaiveDateTime, NaiveDateTime)> {
+        let s = input.trim().to_lowercase();
+        let now = now
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(now);
+        let today = now.date();
+        let midnight = |date: NaiveDate| date.and_time(NaiveTime::MIN);
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let (start, end) = match words.as_slice() {
+            ["this", "week"] => (now, midnight(start_of_week(today) + Duration::weeks(1))),
+            ["next", "week"] => {
+                let monday = start_of_week(today) + Duration::weeks(1);
+                (midnight(monday), midnight(monday + Duration::weeks(1)))
+            }
+            ["next", n, "days" | "day"] => {
+                let n: i64 = n
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("invalid range '{}'", input))?;
+                (now, midnight(today + Duration::days(n + 1)))
+            }
+            _ => {
+                let start = self.parse_at(input, now)?;
+                (start, midnight(start.date() + Duration::days(1)))
+            }
+        };
+        if end <= now {
+            anyhow::bail!("'{}' is in the past", input);
+        }
+        Ok((start.max(now), end))
+    }
+}
+
+/// Leading day words -> date and the remaining words. A bare time
+/// (`15:30`) means today.
+fn parse_day<'a>(words: &'a [&'a str], today: NaiveDate) -> Option<(NaiveDate, &'a [&'a str])> {
+    match words {
+        ["today", rest @ ..] => Some((today, rest)),
+        ["tomorrow", rest @ ..] => Some((today + Duration::days(1), rest)),
+        ["yesterday", rest @ ..] => Some((today - Duration::days(1), rest)),
+        ["next", "week", rest @ ..] => Some((start_of_week(today) + Duration::weeks(1), rest)),
+        ["next", day, rest @ ..] => {
+            let day: Weekday = day.parse().ok()?;
+            Some((next_weekday(today, day, true), rest))
+        }
+        ["this", day, rest @ ..] => {
+            let day: Weekday = day.parse().ok()?;
+            Some((next_weekday(today, day, false), rest))
+        }
+        [first, rest @ ..] => {
+            if let Ok(day) = first.parse::<Weekday>() {
+                Some((next_weekday(today, day, false), rest))
+            } else if let Ok(date) = NaiveDate::parse_from_str(first, "%Y-%m-%d") {
+                Some((date, rest))
+            } else {
+                parse_time(first).map(|_| (today, words))
+            }
+        }
+        [] => None,
+    }
+}
+
+/// Format a wall-clock time for Graph
+pub fn format(dt: NaiveDateTime) -> String {
+    dt.format(GRAPH_FORMAT).to_string()
+}
+
+/// Format an instant as a UTC query parameter (`calendarView`, `getSchedule`)
+pub fn format_utc(dt: DateTime<Utc>) -> String {
+    dt.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// Graph `dateTime` (`2024-01-15T10:00:00.0000000`) -> wall-clock time
+fn parse_graph(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+fn parse_iso(s: &str) -> Option<NaiveDateTime> {
+    for fmt in ["%Y-%m-%dt%H:%M:%S%.f", "%Y-%m-%dt%H:%M", "%Y-%m-%d %H:%M"] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) {
+            return Some(dt);
+        }
+    }
+    None
+}
+
+/// `10:00`, `9`, `9am`, `3:30pm`, `noon`
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    if s == "noon" {
+        return NaiveTime::from_hms_opt(12, 0, 0);
+    }
+    let (s, pm) = if let Some(s) = s.strip_suffix("am") {
+        (s, Some(false))
+    } else if let Some(s) = s.strip_suffix("pm") {
+        (s, Some(true))
+    } else {
+        (s, None)
+    };
+    let (hour, minute) = match s.split_once(':') {
+        Some((h, m)) => (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?),
+        None => (s.parse::<u32>().ok()?, 0),
+    };
+    let hour = match pm {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(pm) => hour % 12 + if pm { 12 } else { 0 },
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// `45m`, `2h`, `1h30m`, `1d`, `1w`; a bare number is minutes
+pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+    let s = s.trim().to_lowercase();
+    let invalid = || anyhow::anyhow!("invalid duration '{}' (e.g. 45m, 1h30m, 2d)", s);
+    if let Ok(minutes) = s.parse::<i64>() {
+        if minutes <= 0 {
+            return Err(invalid());
+        }
+        return Ok(Duration::minutes(minutes));
+    }
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in s.chars().filter(|c| !c.is_whitespace()) {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let n: i64 = number.parse().map_err(|_| invalid())?;
+        number.clear();
+        total += match c {
+            'w' => Duration::weeks(n),
+            'd' => Duration::days(n),
+            'h' => Duration::hours(n),
+            'm' => Duration::minutes(n),
+            _ => return Err(invalid()),
+        };
+    }
+    if !number.is_empty() || total <= Duration::zero() {
+        return Err(invalid());
+    }
+    Ok(total)
+}
+
+pub fn start_of_week(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// The next `day` on or after `from` (strictly after when `skip_today`)
+fn next_weekday(from: NaiveDate, day: Weekday, skip_today: bool) -> NaiveDate {
+    let ahead = (day.num_days_from_monday() + 7 - from.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead == 0 && skip_today { 7 } else { ahead };
+    from + Duration::days(ahead as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn clock(timezone: &str) -> Clock {
+        Clock::new(timezone).unwrap()
+    }
+
+    /// Wednesday
+    const NOW: &str = "2024-05-15 14:20";
+
+    #[test]
+    fn windows_zones_resolve() {
+        for (windows, iana) in WINDOWS_ZONES {
+            assert!(zone(windows).is_some(), "{} -> {}", windows, iana);
+        }
+        assert_eq!(zone("China Standard Time"), Some(Tz::Asia__Shanghai));
+        assert_eq!(
+            zone("pacific standard time"),
+            Some(Tz::America__Los_Angeles)
+        );
+        assert_eq!(zone("Europe/Paris"), Some(Tz::Europe__Paris));
+        assert!(zone("Mars Standard Time").is_none());
+        assert!(Clock::new("Mars Standard Time").is_err());
+    }
+
+    #[test]
+    fn parse_table() {
+        let clock = clock("UTC");
+        let now = at(NOW);
+        let cases = [
+            ("2024-01-15T10:00", "2024-01-15 10:00"),
+            ("2024-01-15T10:00:00", "2024-01-15 10:00"),
+            ("2024-01-15 10:00", "2024-01-15 10:00"),
+            ("2024-01-15", "2024-01-15 00:00"),
+            ("2024-01-15 9am", "2024-01-15 09:00"),
+            ("2024-01-15T10:00:00+08:00", "2024-01-15 02:00"),
+            ("now", "2024-05-15 14:20"),
+            ("+2h", "2024-05-15 16:20"),
+            ("in 1h30m", "2024-05-15 15:50"),
+            ("today", "2024-05-15 00:00"),
+            ("tomorrow 10:00", "2024-05-16 10:00"),
+            ("yesterday noon", "2024-05-14 12:00"),
+            ("15:30", "2024-05-15 15:30"),
+            ("3pm", "2024-05-15 15:00"),
+            ("10 am", "2024-05-15 10:00"),
+            ("12am", "2024-05-15 00:00"),
+            ("friday 15:30", "2024-05-17 15:30"),
+            ("wednesday", "2024-05-15 00:00"),
+            ("this wednesday", "2024-05-15 00:00"),
+            ("next wednesday 9am", "2024-05-22 09:00"),
+            ("next monday 9am", "2024-05-20 09:00"),
+            ("next week", "2024-05-20 00:00"),
+            ("Tomorrow 10:00", "2024-05-16 10:00"),
+        ];
+        for (input, expected) in cases {
+            let parsed = clock.parse_at(input, now);
+            assert_eq!(parsed.ok(), Some(at(expected)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_rejects_invalid_input() {
+        let clock = clock("UTC");
+        let now = at(NOW);
+        for input in [
+            "",
+            "soon",
+            "tomorrow 25:00",
+            "13pm",
+            "0am",
+            "next 10:00",
+            "+",
+            "+0m",
+            "+2x",
+            "tomorrow at 10",
+            "2024-13-01",
+        ] {
+            assert!(clock.parse_at(input, now).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_range_table() {
+        let clock = clock("UTC");
+        let now = at(NOW);
+        let cases = [
+            ("this week", "2024-05-15 14:20", "2024-05-20 00:00"),
+            ("next week", "2024-05-20 00:00", "2024-05-27 00:00"),
+            ("next 3 days", "2024-05-15 14:20", "2024-05-19 00:00"),
+            ("tomorrow", "2024-05-16 00:00", "2024-05-17 00:00"),
+            ("today", "2024-05-15 14:20", "2024-05-16 00:00"),
+            ("next monday", "2024-05-20 00:00", "2024-05-21 00:00"),
+        ];
+        for (input, start, end) in cases {
+            let range = clock.parse_range_at(input, now);
+            assert_eq!(range.ok(), Some((at(start), at(end))), "{}", input);
+        }
+        for input in ["yesterday", "next x days", "whenever"] {
+            assert!(clock.parse_range_at(input, now).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn parse_duration_table() {
+        let cases = [
+            ("45m", 45),
+            ("45", 45),
+            ("2h", 120),
+            ("1h30m", 90),
+            ("1h 30m", 90),
+            ("1H", 60),
+            ("1d", 1440),
+            ("1w", 10080),
+        ];
+        for (input, minutes) in cases {
+            assert_eq!(
+                parse_duration(input).ok(),
+                Some(Duration::minutes(minutes)),
+                "{}",
+                input
+            );
+        }
+        for input in ["", "0", "-5", "0m", "m", "1x", "1h30", "1.5h", "日"] {
+            assert!(parse_duration(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn to_utc_handles_dst() {
+        let clock = clock("Pacific Standard Time");
+        let utc = |s: &str| clock.to_utc(at(s)).format("%Y-%m-%d %H:%M").to_string();
+        // PST and PDT
+        assert_eq!(utc("2024-01-15 09:00"), "2024-01-15 17:00");
+        assert_eq!(utc("2024-07-15 09:00"), "2024-07-15 16:00");
+        // 02:30 does not exist on 2024-03-10: shifted past the gap
+        assert_eq!(utc("2024-03-10 02:30"), "2024-03-10 10:30");
+        // 01:30 happens twice on 2024-11-03: the earlier instant (PDT)
+        assert_eq!(utc("2024-11-03 01:30"), "2024-11-03 08:30");
+        assert_eq!(
+            clock.wall_clock(clock.to_utc(at("2024-07-15 09:00"))),
+            at("2024-07-15 09:00")
+        );
+    }
+
+    #[test]
+    fn convert_between_zones() {
+        let clock = clock("China Standard Time");
+        let dt = DateTimeTimeZone {
+            date_time: "2024-05-15T06:00:00.0000000".to_string(),
+            time_zone: "UTC".to_string(),
+        };
+        assert_eq!(clock.convert(&dt), Some(at("2024-05-15 14:00")));
+        let unknown = DateTimeTimeZone {
+            date_time: "2024-05-15T06:00:00".to_string(),
+            time_zone: "Nowhere".to_string(),
+        };
+        assert_eq!(clock.convert(&unknown), None);
+    }
+}
@@ -0,0 +1,397 @@
+//! Draft management: show, edit, delete and attachments
+//!
+//! `edit` opens the draft in `$VISUAL`/`$EDITOR` as a header block followed
+//! by a blank line and the body, then PATCHes the draft with the result.
+//!
+//! Files up to [`SMALL_ATTACHMENT_LIMIT`] are attached in a single request.
+//! Larger files go through a Graph upload session in chunks; the session URL
+//! is kept in the temp directory, keyed on the draft and file, so an
+//! interrupted upload resumes from the server's next expected range on the
+//! next run.
+
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use clap::Subcommand;
+use fafafa_outlook_core::{DraftMessage, Message, OutlookClient, Recipient};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Largest attachment sent inline (Graph's limit for a single request is 3 MB)
+const SMALL_ATTACHMENT_LIMIT: u64 = 3 * 1024 * 1024;
+
+/// Upload chunk size; Graph requires a multiple of 320 KiB
+const CHUNK_SIZE: u64 = 10 * 320 * 1024;
+
+#[derive(Subcommand)]
+pub enum DraftCommand {
+    /// Show a draft's recipients, subject and body
+    Show {
+        /// Draft ID
+        id: String,
+    },
+    /// Edit a draft in $EDITOR
+    Edit {
+        /// Draft ID
+        id: String,
+    },
+    /// Delete a draft
+    Delete {
+        /// Draft ID
+        id: String,
+    },
+    /// Attach files to a draft
+    Attach {
+        /// Draft ID
+        draft_id: String,
+        /// Files to attach
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Remove an attachment from a draft
+    Detach {
+        /// Draft ID
+        draft_id: String,
+        /// Attachment ID
+        attachment_id: String,
+    },
+    /// List a draft's attachments
+    Attachments {
+        /// Draft ID
+        draft_id: String,
+    },
+}
+
+pub async fn run(client: &OutlookClient, command: DraftCommand) -> anyhow::Result<()> {
+    match command {
+        DraftCommand::Show { id } => {
+            let draft = client.get_message_full(&id).await?;
+            println!("{}", to_editable(&draft));
+        }
+        DraftCommand::Edit { id } => {
+            edit(client, &id).await?;
+        }
+        DraftCommand::Delete { id } => {
+            client.delete_message(&id).await?;
+            println!("Draft deleted: {}", id);
+        }
+        DraftCommand::Attach { draft_id, paths } => {
+            for path in paths {
+                attach(client, &draft_id, Path::new(&path)).await?;
+            }
+        }
+        DraftCommand::Detach {
+            draft_id,
+            attachment_id,
+        } => {
+            client.delete_attachment(&draft_id, &attachment_id).await?;
+            println!("Attachment removed: {}", attachment_id);
+        }
+        DraftCommand::Attachments { draft_id } => {
+            let attachments = client.list_attachments(&draft_id).await?;
+            if attachments.is_empty() {
+                println!("No attachments");
+            } else {
+                println!("Attachments:");
+                for att in attachments {
+                    let size = att
+                        .size
+                        .map(|s| format!(" ({} bytes)", s))
+                        .unwrap_or_default();
+                    println!("  {} - {}{}", att.id, att.name, size);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Open a draft in the user's editor and save the changes back
+pub async fn edit(client: &OutlookClient, id: &str) -> anyhow::Result<()> {
+    let draft = client.get_message_full(id).await?;
+    let original = to_editable(&draft);
+
+    let path = create_temp_file(&original)?;
+    let status = open_editor(&path);
+    let edited = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    let edited = edited?;
+    if !status?.success() {
+        anyhow::bail!("editor exited with an error; draft not changed");
+    }
+
+    if edited == original {
+        println!("No changes");
+        return Ok(());
+    }
+    let update = parse_editable(&edited)?;
+    client.update_draft(id, update).await?;
+    println!("Draft updated: {}", id);
+    Ok(())
+}
+
+/// Write `contents` to a new temp file only the current user can read
+fn create_temp_file(contents: &str) -> anyhow::Result<PathBuf> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let dir = std::env::temp_dir();
+    for attempt in 0..100 {
+        let path = dir.join(format!(
+            "outlook-draft-{}-{:x}-{}.txt",
+            std::process::id(),
+            nonce,
+            attempt
+        ));
+        let mut file = match options.open(&path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if let Err(e) = file.write_all(contents.as_bytes()) {
+            let _ = std::fs::remove_file(&path);
+            return Err(e.into());
+        }
+        return Ok(path);
+    }
+    anyhow::bail!("cannot create a temporary file in {}", dir.display())
+}
+
+fn open_editor(path: &Path) -> anyhow::Result<std::process::ExitStatus> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| {
+            if cfg!(windows) {
+                "notepad".to_string()
+            } else {
+                "vi".to_string()
+            }
+        });
+    // Allow editors with arguments, e.g. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let program = parts
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("$EDITOR is empty"))?;
+    std::process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("cannot start editor '{}': {}", program, e))
+}
+
+fn addresses(recipients: Option<&[Recipient]>) -> String {
+    recipients
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.email_address.address.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Render a draft as an editable header block and body
+fn to_editable(draft: &Message) -> String {
+    let (format, body) = match &draft.body {
+        Some(b) if b.content_type.eq_ignore_ascii_case("html") => ("html", b.content.as_str()),
+        Some(b) => ("text", b.content.as_str()),
+        None => ("text", ""),
+    };
+    format!(
+        "To: {}\nCc: {}\nBcc: {}\nSubject: {}\nFormat: {}\n\n{}",
+        addresses(draft.to_recipients.as_deref()),
+        addresses(draft.cc_recipients.as_deref()),
+        addresses(draft.bcc_recipients.as_deref()),
+        draft.subject.as_deref().unwrap_or(""),
+        format,
+        body
+    )
+}
+
+/// Parse the edited header block and body back into a draft update
+fn parse_editable(text: &str) -> anyhow::Result<DraftMessage> {
+    let text = text.replace("\r\n", "\n");
+    let (head, body) = text.split_once("\n\n").unwrap_or((&text, ""));
+    let mut draft = DraftMessage::new();
+    let mut html = false;
+    for line in head.lines() {
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("invalid header line: {}", line))?;
+        let value = value.trim();
+        let list: Vec<&str> = value
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .collect();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "to" => draft = draft.to(&list),
+            "cc" => draft = draft.cc(&list),
+            "bcc" => draft = draft.bcc(&list),
+            "subject" => draft = draft.subject(value),
+            "format" => html = value.eq_ignore_ascii_case("html"),
+            other => anyhow::bail!("unknown header: {}", other),
+        }
+    }
+    draft = if html {
+        draft.body_html(body)
+    } else {
+        draft.body_text(body)
+    };
+    Ok(draft)
+}
+
+async fn attach(client: &OutlookClient, draft_id: &str, path: &Path) -> anyhow::Result<()> {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| anyhow::anyhow!("invalid file name: {}", path.display()))?;
+    let size = std::fs::metadata(path)
+        .map_err(|e| anyhow::anyhow!("cannot read {}: {}", path.display(), e))?
+        .len();
+
+    if size <= SMALL_ATTACHMENT_LIMIT {
+        let bytes = std::fs::read(path)?;
+        let att = client
+            .add_file_attachment(draft_id, name, content_type(path), &bytes)
+            .await?;
+        println!("Attached: {} ({} bytes)", name, size);
+        println!("  ID: {}", att.id);
+    } else {
+        upload_large(client, draft_id, path, name, size).await?;
+        println!("Attached: {} ({} bytes)", name, size);
+    }
+    Ok(())
+}
+
+/// Upload session state persisted between runs
+#[derive(Serialize, Deserialize)]
+struct SessionState {
+    draft_id: String,
+    upload_url: String,
+}
+
+/// State file for uploading `path` (of `size` bytes) to `draft_id`
+fn state_path(draft_id: &str, path: &Path, size: u64) -> PathBuf {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut hasher = Sha256::new();
+    hasher.update(draft_id.as_bytes());
+    hasher.update([0]);
+    hasher.update(path.as_os_str().as_encoded_bytes());
+    hasher.update(size.to_le_bytes());
+    std::env::temp_dir()
+        .join("outlook-cli-uploads")
+        .join(format!("{}.json", hex::encode(hasher.finalize())))
+}
+
+fn save_state(state_file: &Path, state: &SessionState) -> anyhow::Result<()> {
+    if let Some(dir) = state_file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(state_file, serde_json::to_string(state)?)?;
+    Ok(())
+}
+
+async fn upload_large(
+    client: &OutlookClient,
+    draft_id: &str,
+    path: &Path,
+    name: &str,
+    size: u64,
+) -> anyhow::Result<()> {
+    let state_file = state_path(draft_id, path, size);
+    let saved: Option<SessionState> = std::fs::read_to_string(&state_file)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .filter(|s: &SessionState| s.draft_id == draft_id);
+
+    // Resume a saved session if the server still knows it
+    let mut offset = 0;
+    let mut upload_url = None;
+    if let Some(state) = saved {
+        if let Ok(session) = client.get_upload_session(&state.upload_url).await {
+            offset = next_offset(&session.next_expected_ranges);
+            upload_url = Some(state.upload_url);
+            eprintln!("Resuming upload of {} at byte {}", name, offset);
+        }
+    }
+    let upload_url = match upload_url {
+        Some(url) => url,
+        None => {
+            let session = client
+                .create_attachment_upload_session(draft_id, name, size)
+                .await?;
+            let state = SessionState {
+                draft_id: draft_id.to_string(),
+                upload_url: session.upload_url.clone(),
+            };
+            if let Err(e) = save_state(&state_file, &state) {
+                eprintln!(
+                    "Warning: cannot save upload state to {} ({}); an interrupted upload will restart",
+                    state_file.display(),
+                    e
+                );
+            }
+            session.upload_url
+        }
+    };
+
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    while offset < size {
+        let len = CHUNK_SIZE.min(size - offset);
+        let mut chunk = vec![0; len as usize];
+        file.read_exact(&mut chunk)?;
+        client
+            .upload_attachment_chunk(&upload_url, offset, size, &chunk)
+            .await?;
+        offset += len;
+        eprint!("\r  {}: {}%", name, offset * 100 / size);
+    }
+    eprintln!();
+
+    let _ = std::fs::remove_file(&state_file);
+    Ok(())
+}
+
+/// Start of the first range in Graph's `nextExpectedRanges` (e.g. `"3276800-"`)
+fn next_offset(ranges: &[String]) -> u64 {
+    ranges
+        .first()
+        .and_then(|r| r.split('-').next())
+        .and_then(|start| start.parse().ok())
+        .unwrap_or(0)
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "json" => "application/json",
+        "txt" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "doc" => "application/msword",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xls" => "application/vnd.ms-excel",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "ppt" => "application/vnd.ms-powerpoint",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "eml" => "message/rfc822",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,988 @@
+//! iCalendar (RFC 5545) export
+//!
+//! Events are fetched through the calendar view for the requested range.
+//! Occurrences of a recurring series are replaced by the series master with
+//! its RRULE, and modified occurrences are written as overrides with a
+//! RECURRENCE-ID under the series UID. Occurrences the view leaves out
+//! (deleted from the series) become EXDATEs. Times are converted to the
+//! zone the event or series was created in (mapped to its IANA name) with a
+//! VTIMEZONE describing the offsets in use, so RRULEs expand across DST
+//! changes; UTC and unknown zones are written in UTC.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::{OffsetComponents, Tz};
+use clap::{Args, ValueEnum};
+use fafafa_outlook_core::{
+    Attendee, CalendarEvent, DateTimeTimeZone, OutlookClient, PatternedRecurrence,
+};
+
+use crate::calendars;
+use crate::dates::{self, Clock};
+use crate::settings;
+
+const PRODID: &str = "-//fafafa//outlook-cli//EN";
+
+/// Days exported when no --end is given
+const DEFAULT_EXPORT_DAYS: i64 = 30;
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value = "ics")]
+    format: ExportFormat,
+    /// Start of the range (e.g. 2024-01-01, today, 'next monday'; default: today)
+    #[arg(long)]
+    start: Option<String>,
+    /// End of the range (default: 30 days after the start)
+    #[arg(long)]
+    end: Option<String>,
+    /// Calendar name or ID (default: your default calendar)
+    #[arg(long)]
+    calendar: Option<String>,
+    /// Write to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Maximum number of events to fetch
+    #[arg(short, long, default_value = "1000")]
+    limit: u32,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// iCalendar (RFC 5545)
+    Ics,
+}
+
+pub async fn export(client: &OutlookClient, args: ExportArgs) -> anyhow::Result<()> {
+    let calendar = calendars::resolve(client, args.calendar.as_deref()).await?;
+    let clock = Clock::new(&settings::mailbox_timezone(client).await?)?;
+    let start = match &args.start {
+        Some(s) => clock.parse(s)?,
+        None => clock.today().and_time(NaiveTime::MIN),
+    };
+    let end = match &args.end {
+        Some(e) => clock.parse(e)?,
+        None => start + Duration::days(DEFAULT_EXPORT_DAYS),
+    };
+    if end <= start {
+        anyhow::bail!("end must be after start");
+    }
+
+    let view = client
+        .list_events_range_in(
+            &calendar,
+            &dates::format_utc(clock.to_utc(start)),
+            &dates::format_utc(clock.to_utc(end)),
+            args.limit,
+        )
+        .await?;
+
+    // A full view lists every occurrence in the range, so any the series
+    // expects but the view lacks were deleted
+    let complete = view.len() < args.limit as usize;
+    if !complete {
+        eprintln!(
+            "Warning: reached --limit {}; deleted occurrences are not excluded",
+            args.limit
+        );
+    }
+
+    // Plain occurrences are covered by their master's RRULE
+    let mut events = Vec::new();
+    let mut masters = BTreeSet::new();
+    let mut seen: BTreeMap<String, Vec<DateTime<Utc>>> = BTreeMap::new();
+    for event in view {
+        let original = event
+            .original_start
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .or_else(|| event.start.as_ref().and_then(instant));
+        if let (Some(master), Some(original)) = (&event.series_master_id, original) {
+            seen.entry(master.clone()).or_default().push(original);
+        }
+        match event.event_type.as_deref() {
+            Some("occurrence") => {
+                masters.extend(event.series_master_id.clone());
+            }
+            Some("exception") => {
+                masters.extend(event.series_master_id.clone());
+                events.push(event);
+            }
+            _ => events.push(event),
+        }
+    }
+    for id in &masters {
+        if !events.iter().any(|e| &e.id == id) {
+            events.push(client.get_event_in(&calendar, id).await?);
+        }
+    }
+
+    let mut exdates = BTreeMap::new();
+    if complete {
+        let range = (clock.to_utc(start), clock.to_utc(end));
+        for event in events.iter().filter(|e| masters.contains(&e.id)) {
+            let seen = seen.get(&event.id).map(Vec::as_slice).unwrap_or(&[]);
+            let missing = missing_occurrences(event, seen, range);
+            if !missing.is_empty() {
+                exdates.insert(event.id.clone(), missing);
+            }
+        }
+    }
+
+    let ics = match args.format {
+        ExportFormat::Ics => to_ics(&events, &exdates, end.date()),
+    };
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, &ics)?;
+            eprintln!("Exported {} events to {}", events.len(), path.display());
+        }
+        None => print!("{}", ics),
+    }
+    Ok(())
+}
+
+/// Serialize events as a VCALENDAR. `exdates` holds the deleted
+/// occurrences of each series master; recurring events' timezones are
+/// described through `range_end`.
+fn to_ics(
+    events: &[CalendarEvent],
+    exdates: &BTreeMap<String, Vec<NaiveDateTime>>,
+    range_end: NaiveDate,
+) -> String {
+    let mut out = Ics::default();
+    out.line("BEGIN:VCALENDAR");
+    out.line("VERSION:2.0");
+    out.line(&format!("PRODID:{}", PRODID));
+    out.line("CALSCALE:GREGORIAN");
+    out.line("METHOD:PUBLISH");
+
+    // Timezones in use and the span of dates they must cover
+    let mut zones: BTreeMap<String, (Tz, NaiveDate, NaiveDate)> = BTreeMap::new();
+    for event in events {
+        if event.is_all_day.unwrap_or(false) {
+            continue;
+        }
+        let Some(tz) = series_zone(event) else {
+            continue;
+        };
+        for dt in [&event.start, &event.end].into_iter().flatten() {
+            if let Some(utc) = instant(dt) {
+                let date = utc.with_timezone(&tz).date_naive();
+                let until = if event.recurrence.is_some() {
+                    recurrence_end(event).unwrap_or(range_end).max(range_end)
+                } else {
+                    date
+                };
+                let entry = zones
+                    .entry(tz.name().to_string())
+                    .or_insert((tz, date, until));
+                entry.1 = entry.1.min(date);
+                entry.2 = entry.2.max(until).max(date);
+            }
+        }
+    }
+    for (tz, from, to) in zones.values() {
+        write_vtimezone(&mut out, *tz, *from, *to);
+    }
+
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    for event in events {
+        let excluded = exdates.get(&event.id).map(Vec::as_slice).unwrap_or(&[]);
+        write_vevent(&mut out, event, excluded, &stamp);
+    }
+    out.line("END:VCALENDAR");
+    out.text
+}
+
+fn write_vevent(out: &mut Ics, event: &CalendarEvent, exdates: &[NaiveDateTime], stamp: &str) {
+    let all_day = event.is_all_day.unwrap_or(false);
+    out.line("BEGIN:VEVENT");
+    // `uid` is shared by a series and its occurrences; `iCalUId` is not
+    let uid = event
+        .uid
+        .as_deref()
+        .or(event.i_cal_uid.as_deref())
+        .unwrap_or(&event.id);
+    out.line(&format!("UID:{}", escape(uid)));
+    out.line(&format!("DTSTAMP:{}", stamp));
+    let zone = series_zone(event);
+    if let Some(start) = &event.start {
+        out.line(&format!("DTSTART{}", date_value(start, zone, all_day)));
+    }
+    if let Some(end) = &event.end {
+        out.line(&format!("DTEND{}", date_value(end, zone, all_day)));
+    }
+    if let Some(original) = event
+        .original_start
+        .as_deref()
+        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+    {
+        let original = wall(original.with_timezone(&Utc), zone);
+        out.line(&format!(
+            "RECURRENCE-ID{}",
+            naive_value(original, zone, all_day)
+        ));
+    }
+    if let Some(recurrence) = &event.recurrence {
+        out.line(&format!("RRULE:{}", rrule(recurrence, zone, all_day)));
+        for exdate in exdates {
+            out.line(&format!("EXDATE{}", naive_value(*exdate, zone, all_day)));
+        }
+    }
+    out.line(&format!(
+        "SUMMARY:{}",
+        escape(event.subject.as_deref().unwrap_or(""))
+    ));
+    if let Some(location) = event
+        .location
+        .as_ref()
+        .and_then(|l| l.display_name.as_deref())
+        .filter(|l| !l.is_empty())
+    {
+        out.line(&format!("LOCATION:{}", escape(location)));
+    }
+    let join_url = event
+        .online_meeting
+        .as_ref()
+        .and_then(|m| m.join_url.as_deref());
+    let mut description = event.body_preview.clone().unwrap_or_default();
+    if let Some(url) = join_url {
+        if !description.is_empty() {
+            description.push_str("\n\n");
+        }
+        description.push_str(&format!("Join: {}", url));
+        out.line(&format!("URL:{}", url));
+    }
+    if !description.is_empty() {
+        out.line(&format!("DESCRIPTION:{}", escape(&description)));
+    }
+    if let Some(organizer) = &event.organizer {
+        let addr = &organizer.email_address;
+        out.line(&format!(
+            "ORGANIZER{}:mailto:{}",
+            common_name(addr.name.as_deref()),
+            addr.address
+        ));
+    }
+    for attendee in event.attendees.iter().flatten() {
+        out.line(&attendee_line(attendee));
+    }
+    if event.is_cancelled.unwrap_or(false) {
+        out.line("STATUS:CANCELLED");
+    } else {
+        out.line("STATUS:CONFIRMED");
+    }
+    if event.show_as.as_deref() == Some("free") {
+        out.line("TRANSP:TRANSPARENT");
+    } else {
+        out.line("TRANSP:OPAQUE");
+    }
+    out.line("END:VEVENT");
+}
+
+fn attendee_line(attendee: &Attendee) -> String {
+    let role = match attendee.attendee_type.as_deref() {
+        Some("optional") => "OPT-PARTICIPANT",
+        Some("resource") => "NON-PARTICIPANT",
+        _ => "REQ-PARTICIPANT",
+    };
+    let partstat = match attendee.status.as_ref().and_then(|s| s.response.as_deref()) {
+        Some("accepted") | Some("organizer") => "ACCEPTED",
+        Some("declined") => "DECLINED",
+        Some("tentativelyAccepted") => "TENTATIVE",
+        _ => "NEEDS-ACTION",
+    };
+    let addr = &attendee.email_address;
+    format!(
+        "ATTENDEE{};ROLE={};PARTSTAT={}:mailto:{}",
+        common_name(addr.name.as_deref()),
+        role,
+        partstat,
+        addr.address
+    )
+}
+
+fn common_name(name: Option<&str>) -> String {
+    match name.map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        // Parameter values with special characters must be quoted; DQUOTE
+        // itself is not allowed
+        Some(name) => format!(";CN=\"{}\"", name.replace('"', "'")),
+        None => String::new(),
+    }
+}
+
+/// A Graph date/time written in `zone`: `;VALUE=DATE:20240115`,
+/// `;TZID=Asia/Shanghai:20240115T100000` or, without a zone,
+/// `:20240115T020000Z`. All-day dates are kept as they are.
+fn date_value(dt: &DateTimeTimeZone, zone: Option<Tz>, all_day: bool) -> String {
+    let naive = if all_day {
+        parse_naive(&dt.date_time)
+    } else {
+        instant(dt).map(|utc| wall(utc, zone))
+    };
+    match naive {
+        Some(naive) => naive_value(naive, zone, all_day),
+        None => format!(":{}", dt.date_time),
+    }
+}
+
+/// A wall-clock time in `zone` (UTC when `None`)
+fn naive_value(naive: NaiveDateTime, zone: Option<Tz>, all_day: bool) -> String {
+    if all_day {
+        return format!(";VALUE=DATE:{}", naive.format("%Y%m%d"));
+    }
+    match zone {
+        Some(tz) => format!(";TZID={}:{}", tz.name(), naive.format("%Y%m%dT%H%M%S")),
+        None => format!(":{}", naive.format("%Y%m%dT%H%M%SZ")),
+    }
+}
+
+/// The zone an event or series was created in: the recurrence zone, the
+/// original start zone, then the zone of `start` (UTC as returned by Graph).
+/// `None` for UTC.
+fn series_zone(event: &CalendarEvent) -> Option<Tz> {
+    let recurrence = event
+        .recurrence
+        .as_ref()
+        .and_then(|r| r.range.recurrence_time_zone.as_deref());
+    let start = event.start.as_ref().map(|s| s.time_zone.as_str());
+    [recurrence, event.original_start_time_zone.as_deref(), start]
+        .into_iter()
+        .flatten()
+        .find_map(dates::zone)
+        .filter(|tz| !is_utc(*tz))
+}
+
+/// Zones written as plain UTC rather than with a VTIMEZONE
+fn is_utc(tz: Tz) -> bool {
+    matches!(
+        tz.name(),
+        "UTC"
+            | "UCT"
+            | "GMT"
+            | "Universal"
+            | "Zulu"
+            | "Etc/UTC"
+            | "Etc/UCT"
+            | "Etc/GMT"
+            | "Etc/Universal"
+            | "Etc/Zulu"
+    )
+}
+
+/// A Graph date/time as an instant; an unknown zone is read as UTC
+fn instant(dt: &DateTimeTimeZone) -> Option<DateTime<Utc>> {
+    let naive = parse_naive(&dt.date_time)?;
+    Some(match Clock::new(&dt.time_zone) {
+        Ok(clock) => clock.to_utc(naive),
+        Err(_) => naive.and_utc(),
+    })
+}
+
+/// Wall-clock time of `utc` in `zone` (UTC when `None`)
+fn wall(utc: DateTime<Utc>, zone: Option<Tz>) -> NaiveDateTime {
+    match zone {
+        Some(tz) => utc.with_timezone(&tz).naive_local(),
+        None => utc.naive_utc(),
+    }
+}
+
+/// The instant of a wall-clock time in `zone` (UTC when `None`)
+fn to_instant(naive: NaiveDateTime, zone: Option<Tz>) -> Option<DateTime<Utc>> {
+    match zone {
+        Some(tz) => Some(
+            tz.from_local_datetime(&naive)
+                .earliest()?
+                .with_timezone(&Utc),
+        ),
+        None => Some(naive.and_utc()),
+    }
+}
+
+fn parse_naive(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+/// Last date covered by a series with an end date
+fn recurrence_end(event: &CalendarEvent) -> Option<NaiveDate> {
+    let range = &event.recurrence.as_ref()?.range;
+    NaiveDate::parse_from_str(range.end_date.as_deref()?, "%Y-%m-%d").ok()
+}
+
+/// Occurrences of a series master within `range` (UTC) that are not in
+/// `seen` (the original starts the calendar view returned), as wall-clock
+/// times in the series' timezone. Matched by date in that zone.
+fn missing_occurrences(
+    master: &CalendarEvent,
+    seen: &[DateTime<Utc>],
+    range: (DateTime<Utc>, DateTime<Utc>),
+) -> Vec<NaiveDateTime> {
+    let (Some(recurrence), Some(start)) = (&master.recurrence, &master.start) else {
+        return Vec::new();
+    };
+    let zone = series_zone(master);
+    let first = if master.is_all_day.unwrap_or(false) {
+        parse_naive(&start.date_time)
+    } else {
+        instant(start).map(|utc| wall(utc, zone))
+    };
+    let Some(first) = first else {
+        return Vec::new();
+    };
+    let seen: BTreeSet<NaiveDate> = seen.iter().map(|utc| wall(*utc, zone).date()).collect();
+    let last = wall(range.1, zone).date();
+    occurrence_dates(recurrence, first.date(), last)
+        .into_iter()
+        .filter(|date| !seen.contains(date))
+        .map(|date| date.and_time(first.time()))
+        .filter(|naive| to_instant(*naive, zone).is_some_and(|utc| utc >= range.0 && utc < range.1))
+        .collect()
+}
+
+/// Dates a Graph recurrence produces from `first` (the series start) through
+/// `last`, following the same rules as the RRULE written for it
+fn occurrence_dates(
+    recurrence: &PatternedRecurrence,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Vec<NaiveDate> {
+    let pattern = &recurrence.pattern;
+    let range = &recurrence.range;
+    let last = match range
+        .end_date
+        .as_deref()
+        .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+    {
+        Some(end) if range.range_type == "endDate" => end.min(last),
+        _ => last,
+    };
+    let count = match range.range_type.as_str() {
+        "numbered" => range.number_of_occurrences.map(|n| n.max(0) as usize),
+        _ => None,
+    };
+    let interval = pattern.interval.max(1) as i64;
+    let mut weekdays: Vec<Weekday> = pattern
+        .days_of_week
+        .iter()
+        .flatten()
+        .filter_map(|d| d.parse().ok())
+        .collect();
+    if weekdays.is_empty() {
+        weekdays.push(first.weekday());
+    }
+    let week_start = pattern
+        .first_day_of_week
+        .as_deref()
+        .and_then(|d| d.parse::<Weekday>().ok())
+        .unwrap_or(Weekday::Mon);
+    let month_of = |n: i64| {
+        let months = first.year() as i64 * 12 + first.month0() as i64 + n * interval;
+        (
+            months.div_euclid(12) as i32,
+            months.rem_euclid(12) as u32 + 1,
+        )
+    };
+    let day_of_month = pattern.day_of_month.map_or(first.day(), |d| d as u32);
+    let month = pattern.month.map_or(first.month(), |m| m as u32);
+
+    let mut dates = Vec::new();
+    for n in 0.. {
+        // First day of the n-th period and the candidate dates in it
+        let (period, candidates) = match pattern.pattern_type.as_str() {
+            "daily" => {
+                let day = first + Duration::days(n * interval);
+                (day, vec![day])
+            }
+            "weekly" => {
+                let back = (first.weekday().num_days_from_monday() + 7
+                    - week_start.num_days_from_monday())
+                    % 7;
+                let week = first - Duration::days(back as i64) + Duration::weeks(n * interval);
+                let days = (0..7)
+                    .map(|i| week + Duration::days(i))
+                    .filter(|d| weekdays.contains(&d.weekday()))
+                    .collect();
+                (week, days)
+            }
+            "absoluteMonthly" | "relativeMonthly" => {
+                let (y, m) = month_of(n);
+                let Some(start) = NaiveDate::from_ymd_opt(y, m, 1) else {
+                    break;
+                };
+                let day = if pattern.pattern_type == "absoluteMonthly" {
+                    NaiveDate::from_ymd_opt(y, m, day_of_month)
+                } else {
+                    nth_weekday(y, m, &weekdays, pattern.index.as_deref())
+                };
+                (start, day.into_iter().collect())
+            }
+            "absoluteYearly" | "relativeYearly" => {
+                let y = first.year() + (n * interval) as i32;
+                let Some(start) = NaiveDate::from_ymd_opt(y, 1, 1) else {
+                    break;
+                };
+                let day = if pattern.pattern_type == "absoluteYearly" {
+                    NaiveDate::from_ymd_opt(y, month, day_of_month)
+                } else {
+                    nth_weekday(y, month, &weekdays, pattern.index.as_deref())
+                };
+                (start, day.into_iter().collect())
+            }
+            _ => break,
+        };
+        if period > last {
+            break;
+        }
+        for date in candidates {
+            if count.is_some_and(|count| dates.len() >= count) || date > last {
+                return dates;
+            }
+            if date >= first {
+                dates.push(date);
+            }
+        }
+    }
+    dates
+}
+
+/// The `index` (first ... fourth, last) day of a month falling on one of
+/// `weekdays`, like BYDAY with BYSETPOS
+fn nth_weekday(
+    year: i32,
+    month: u32,
+    weekdays: &[Weekday],
+    index: Option<&str>,
+) -> Option<NaiveDate> {
+    let days: Vec<NaiveDate> = (1..=31)
+        .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .filter(|d| weekdays.contains(&d.weekday()))
+        .collect();
+    match index {
+        Some("second") => days.get(1),
+        Some("third") => days.get(2),
+        Some("fourth") => days.get(3),
+        Some("last") => days.last(),
+        _ => days.first(),
+    }
+    .copied()
+}
+
+/// Graph patternedRecurrence -> RRULE value
+fn rrule(recurrence: &PatternedRecurrence, zone: Option<Tz>, all_day: bool) -> String {
+    let pattern = &recurrence.pattern;
+    let mut parts = Vec::new();
+    let freq = match pattern.pattern_type.as_str() {
+        "daily" => "DAILY",
+        "weekly" => "WEEKLY",
+        "absoluteMonthly" | "relativeMonthly" => "MONTHLY",
+        _ => "YEARLY",
+    };
+    parts.push(format!("FREQ={}", freq));
+    if pattern.interval > 1 {
+        parts.push(format!("INTERVAL={}", pattern.interval));
+    }
+    let days: Vec<&str> = pattern
+        .days_of_week
+        .iter()
+        .flatten()
+        .filter_map(|d| ical_day(d))
+        .collect();
+    match pattern.pattern_type.as_str() {
+        "weekly" => {
+            if !days.is_empty() {
+                parts.push(format!("BYDAY={}", days.join(",")));
+            }
+            if let Some(first) = pattern.first_day_of_week.as_deref().and_then(ical_day) {
+                parts.push(format!("WKST={}", first));
+            }
+        }
+        "absoluteMonthly" => {
+            if let Some(day) = pattern.day_of_month {
+                parts.push(format!("BYMONTHDAY={}", day));
+            }
+        }
+        "absoluteYearly" => {
+            if let Some(month) = pattern.month {
+                parts.push(format!("BYMONTH={}", month));
+            }
+            if let Some(day) = pattern.day_of_month {
+                parts.push(format!("BYMONTHDAY={}", day));
+            }
+        }
+        "relativeMonthly" | "relativeYearly" => {
+            if pattern.pattern_type == "relativeYearly" {
+                if let Some(month) = pattern.month {
+                    parts.push(format!("BYMONTH={}", month));
+                }
+            }
+            if !days.is_empty() {
+                parts.push(format!("BYDAY={}", days.join(",")));
+            }
+            let pos = match pattern.index.as_deref() {
+                Some("second") => 2,
+                Some("third") => 3,
+                Some("fourth") => 4,
+                Some("last") => -1,
+                _ => 1,
+            };
+            parts.push(format!("BYSETPOS={}", pos));
+        }
+        _ => {}
+    }
+
+    let range = &recurrence.range;
+    match range.range_type.as_str() {
+        "endDate" => {
+            if let Some(end) = range
+                .end_date
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            {
+                if all_day {
+                    parts.push(format!("UNTIL={}", end.format("%Y%m%d")));
+                } else {
+                    // UNTIL must be in UTC when DTSTART has a TZID
+                    let last =
+                        end.and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap_or_default());
+                    if let Some(until) = to_instant(last, zone) {
+                        parts.push(format!("UNTIL={}", until.format("%Y%m%dT%H%M%SZ")));
+                    }
+                }
+            }
+        }
+        "numbered" => {
+            if let Some(n) = range.number_of_occurrences {
+                parts.push(format!("COUNT={}", n));
+            }
+        }
+        _ => {}
+    }
+    parts.join(";")
+}
+
+fn ical_day(day: &str) -> Option<&'static str> {
+    match day.to_ascii_lowercase().as_str() {
+        "monday" => Some("MO"),
+        "tuesday" => Some("TU"),
+        "wednesday" => Some("WE"),
+        "thursday" => Some("TH"),
+        "friday" => Some("FR"),
+        "saturday" => Some("SA"),
+        "sunday" => Some("SU"),
+        _ => None,
+    }
+}
+
+/// VTIMEZONE with one observance per offset change between `from` and `to`
+/// (padded by a year on each side), found by scanning the tz database
+fn write_vtimezone(out: &mut Ics, tz: Tz, from: NaiveDate, to: NaiveDate) {
+    if is_utc(tz) {
+        return;
+    }
+    let scan_start = (from - Duration::days(366))
+        .and_time(NaiveTime::MIN)
+        .and_utc();
+    let scan_end = (to + Duration::days(366))
+        .and_time(NaiveTime::MIN)
+        .and_utc();
+
+    out.line("BEGIN:VTIMEZONE");
+    out.line(&format!("TZID:{}", tz.name()));
+    let initial = tz.offset_from_utc_datetime(&scan_start.naive_utc());
+    write_observance(
+        out,
+        &initial,
+        &initial,
+        scan_start.naive_utc() + offset(&initial),
+    );
+
+    let mut previous = initial;
+    let mut day = scan_start;
+    while day < scan_end {
+        let next = day + Duration::days(1);
+        let current = tz.offset_from_utc_datetime(&next.naive_utc());
+        if offset(&current) != offset(&previous) {
+            let at = find_transition(tz, day, next);
+            // DTSTART is the local time just before the change
+            write_observance(out, &previous, &current, at.naive_utc() + offset(&previous));
+            previous = current;
+        }
+        day = next;
+    }
+    out.line("END:VTIMEZONE");
+}
+
+fn write_observance(
+    out: &mut Ics,
+    from: &chrono_tz::TzOffset,
+    to: &chrono_tz::TzOffset,
+    start: NaiveDateTime,
+) {
+    let kind = if to.dst_offset().is_zero() {
+        "STANDARD"
+    } else {
+        "DAYLIGHT"
+    };
+    out.line(&format!("BEGIN:{}", kind));
+    out.line(&format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")));
+    out.line(&format!("TZOFFSETFROM:{}", utc_offset(from)));
+    out.line(&format!("TZOFFSETTO:{}", utc_offset(to)));
+    out.line(&format!("TZNAME:{}", to));
+    out.line(&format!("END:{}", kind));
+}
+
+/// Binary search for the minute the offset changes in `(lo, hi]`
+fn find_transition(tz: Tz, lo: DateTime<Utc>, hi: DateTime<Utc>) -> DateTime<Utc> {
+    let before = offset(&tz.offset_from_utc_datetime(&lo.naive_utc()));
+    let (mut low, mut high) = (0, (hi - lo).num_minutes());
+    while high - low > 1 {
+        let mid = (low + high) / 2;
+        let at = lo + Duration::minutes(mid);
+        if offset(&tz.offset_from_utc_datetime(&at.naive_utc())) == before {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    lo + Duration::minutes(high)
+}
+
+fn offset(o: &chrono_tz::TzOffset) -> Duration {
+    Duration::seconds(o.fix().local_minus_utc() as i64)
+}
+
+/// `+0800`, `-0430`
+fn utc_offset(o: &chrono_tz::TzOffset) -> String {
+    let secs = o.fix().local_minus_utc();
+    let sign = if secs < 0 { '-' } else { '+' };
+    let secs = secs.abs();
+    format!("{}{:02}{:02}", sign, secs / 3600, secs % 3600 / 60)
+}
+
+/// RFC 5545 TEXT escaping
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Accumulates content lines, folded at 75 octets with CRLF endings
+#[derive(Default)]
+struct Ics {
+    text: String,
+}
+
+impl Ics {
+    fn line(&mut self, line: &str) {
+        let mut width = 0;
+        for c in line.chars() {
+            let len = c.len_utf8();
+            // Continuation lines start with a space, which counts
+            if width + len > 75 {
+                self.text.push_str("\r\n ");
+                width = 1;
+            }
+            self.text.push(c);
+            width += len;
+        }
+        self.text.push_str("\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use fafafa_outlook_core::{RecurrencePattern, RecurrenceRange};
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn recurrence(pattern: RecurrencePattern, range: RecurrenceRange) -> PatternedRecurrence {
+        PatternedRecurrence { pattern, range }
+    }
+
+    fn pattern(pattern_type: &str, interval: i32) -> RecurrencePattern {
+        RecurrencePattern {
+            pattern_type: pattern_type.to_string(),
+            interval,
+            ..Default::default()
+        }
+    }
+
+    fn no_end() -> RecurrenceRange {
+        RecurrenceRange {
+            range_type: "noEnd".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn dates(recurrence: &PatternedRecurrence, first: &str, last: &str) -> Vec<String> {
+        occurrence_dates(recurrence, date(first), date(last))
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn daily_dates() {
+        let daily = recurrence(pattern("daily", 2), no_end());
+        assert_eq!(
+            dates(&daily, "2024-01-30", "2024-02-05"),
+            ["2024-01-30", "2024-02-01", "2024-02-03", "2024-02-05"]
+        );
+    }
+
+    #[test]
+    fn weekly_dates() {
+        let mut weekly = pattern("weekly", 2);
+        weekly.days_of_week = Some(vec!["monday".to_string(), "Wednesday".to_string()]);
+        let weekly = recurrence(weekly, no_end());
+        // Starts on a Wednesday: the Monday of the first week is skipped
+        assert_eq!(
+            dates(&weekly, "2024-05-15", "2024-06-05"),
+            ["2024-05-15", "2024-05-27", "2024-05-29"]
+        );
+    }
+
+    #[test]
+    fn monthly_dates() {
+        let mut absolute = pattern("absoluteMonthly", 1);
+        absolute.day_of_month = Some(31);
+        let absolute = recurrence(absolute, no_end());
+        // Months without a 31st are skipped, as with BYMONTHDAY=31
+        assert_eq!(
+            dates(&absolute, "2024-01-31", "2024-05-31"),
+            ["2024-01-31", "2024-03-31", "2024-05-31"]
+        );
+
+        let mut relative = pattern("relativeMonthly", 1);
+        relative.days_of_week = Some(vec!["friday".to_string()]);
+        relative.index = Some("last".to_string());
+        let relative = recurrence(relative, no_end());
+        assert_eq!(
+            dates(&relative, "2024-01-01", "2024-03-31"),
+            ["2024-01-26", "2024-02-23", "2024-03-29"]
+        );
+    }
+
+    #[test]
+    fn yearly_dates() {
+        let mut absolute = pattern("absoluteYearly", 1);
+        absolute.month = Some(2);
+        absolute.day_of_month = Some(29);
+        let absolute = recurrence(absolute, no_end());
+        assert_eq!(
+            dates(&absolute, "2024-02-29", "2032-12-31"),
+            ["2024-02-29", "2028-02-29", "2032-02-29"]
+        );
+
+        let mut relative = pattern("relativeYearly", 1);
+        relative.month = Some(11);
+        relative.days_of_week = Some(vec!["thursday".to_string()]);
+        relative.index = Some("fourth".to_string());
+        let relative = recurrence(relative, no_end());
+        assert_eq!(
+            dates(&relative, "2024-01-01", "2025-12-31"),
+            ["2024-11-28", "2025-11-27"]
+        );
+    }
+
+    #[test]
+    fn range_limits_dates() {
+        let numbered = recurrence(
+            pattern("daily", 1),
+            RecurrenceRange {
+                range_type: "numbered".to_string(),
+                number_of_occurrences: Some(3),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            dates(&numbered, "2024-05-01", "2024-05-31"),
+            ["2024-05-01", "2024-05-02", "2024-05-03"]
+        );
+
+        let end_date = recurrence(
+            pattern("daily", 1),
+            RecurrenceRange {
+                range_type: "endDate".to_string(),
+                end_date: Some("2024-05-02".to_string()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            dates(&end_date, "2024-05-01", "2024-05-31"),
+            ["2024-05-01", "2024-05-02"]
+        );
+    }
+
+    fn graph(date_time: &str, time_zone: &str) -> DateTimeTimeZone {
+        DateTimeTimeZone {
+            date_time: date_time.to_string(),
+            time_zone: time_zone.to_string(),
+        }
+    }
+
+    #[test]
+    fn utc_zones_are_written_as_utc() {
+        for name in ["UTC", "Etc/UTC", "Coordinated Universal Time", "GMT"] {
+            let tz = dates::zone(name).unwrap();
+            assert!(is_utc(tz), "{}", name);
+        }
+        assert!(!is_utc(dates::zone("GMT Standard Time").unwrap()));
+
+        let start = graph("2024-05-15T09:30:00.0000000", "UTC");
+        let zone = dates::zone("UTC").filter(|tz| !is_utc(*tz));
+        assert_eq!(date_value(&start, zone, false), ":20240515T093000Z");
+        assert_eq!(date_value(&start, zone, true), ";VALUE=DATE:20240515");
+    }
+
+    #[test]
+    fn times_are_converted_to_the_series_zone() {
+        // Graph returns UTC; the series was created in Pacific time
+        let zone = dates::zone("Pacific Standard Time");
+        let winter = graph("2024-01-15T17:00:00.0000000", "UTC");
+        let summer = graph("2024-07-15T16:00:00.0000000", "UTC");
+        assert_eq!(
+            date_value(&winter, zone, false),
+            ";TZID=America/Los_Angeles:20240115T090000"
+        );
+        assert_eq!(
+            date_value(&summer, zone, false),
+            ";TZID=America/Los_Angeles:20240715T090000"
+        );
+        // A time in an unknown zone is read as UTC
+        let unknown = graph("2024-01-15T17:00:00", "Nowhere");
+        assert_eq!(
+            date_value(&unknown, zone, false),
+            ";TZID=America/Los_Angeles:20240115T090000"
+        );
+        assert_eq!(date_value(&unknown, None, false), ":20240115T170000Z");
+    }
+
+    #[test]
+    fn lines_are_escaped_and_folded() {
+        assert_eq!(escape("a;b,c\\d\ne"), "a\\;b\\,c\\\\d\\ne");
+        let mut out = Ics::default();
+        out.line(&format!("SUMMARY:{}", "日".repeat(30)));
+        for line in out.text.split("\r\n").filter(|l| !l.is_empty()) {
+            assert!(line.len() <= 75, "{:?}", line);
+        }
+        assert_eq!(
+            out.text.replace("\r\n ", ""),
+            format!("SUMMARY:{}\r\n", "日".repeat(30))
+        );
+    }
+}
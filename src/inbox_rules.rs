@@ -0,0 +1,141 @@
+//! Server-side inbox rules (Graph `messageRules`)
+//!
+//! Rule definitions for `create` use the Graph messageRule schema, in JSON
+//! or TOML (chosen by file extension):
+//!
+//! ```toml
+//! displayName = "Invoices"
+//! sequence = 1
+//! isEnabled = true
+//!
+//! [conditions]
+//! subjectContains = ["invoice"]
+//! hasAttachments = true
+//!
+//! [actions]
+//! moveToFolder = "FOLDER_ID"
+//! stopProcessingRules = true
+//! ```
+
+use std::path::Path;
+
+use clap::Subcommand;
+use fafafa_outlook_core::{NewMessageRule, OutlookClient};
+
+#[derive(Subcommand)]
+pub enum InboxRulesCommand {
+    /// List inbox rules
+    List,
+    /// Show an inbox rule with its conditions and actions
+    Get {
+        /// Rule ID
+        id: String,
+    },
+    /// Create an inbox rule from a JSON or TOML definition
+    Create {
+        /// Rule definition file (.json or .toml)
+        file: String,
+    },
+    /// Delete an inbox rule
+    Delete {
+        /// Rule ID
+        id: String,
+    },
+    /// Enable an inbox rule
+    Enable {
+        /// Rule ID
+        id: String,
+    },
+    /// Disable an inbox rule
+    Disable {
+        /// Rule ID
+        id: String,
+    },
+}
+
+pub async fn run(client: &OutlookClient, command: InboxRulesCommand) -> anyhow::Result<()> {
+    match command {
+        InboxRulesCommand::List => {
+            let rules = client.list_message_rules().await?;
+            if rules.is_empty() {
+                println!("No inbox rules");
+            } else {
+                println!("Inbox Rules:");
+                for rule in rules {
+                    let state = if rule.is_enabled.unwrap_or(false) {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    };
+                    println!(
+                        "  {:>3}. {} ({})",
+                        rule.sequence.unwrap_or(0),
+                        rule.display_name.as_deref().unwrap_or("(no name)"),
+                        state
+                    );
+                    println!("    ID: {}", rule.id);
+                }
+            }
+        }
+        InboxRulesCommand::Get { id } => {
+            let rule = client.get_message_rule(&id).await?;
+            println!(
+                "Name: {}",
+                rule.display_name.as_deref().unwrap_or("(no name)")
+            );
+            println!("Sequence: {}", rule.sequence.unwrap_or(0));
+            println!(
+                "Enabled: {}",
+                if rule.is_enabled.unwrap_or(false) {
+                    "Yes"
+                } else {
+                    "No"
+                }
+            );
+            if let Some(conditions) = &rule.conditions {
+                println!("Conditions:");
+                println!("{}", serde_json::to_string_pretty(conditions)?);
+            }
+            if let Some(exceptions) = &rule.exceptions {
+                println!("Exceptions:");
+                println!("{}", serde_json::to_string_pretty(exceptions)?);
+            }
+            if let Some(actions) = &rule.actions {
+                println!("Actions:");
+                println!("{}", serde_json::to_string_pretty(actions)?);
+            }
+        }
+        InboxRulesCommand::Create { file } => {
+            let rule = load_definition(Path::new(&file))?;
+            let created = client.create_message_rule(rule).await?;
+            println!("Inbox rule created: {}", created.id);
+        }
+        InboxRulesCommand::Delete { id } => {
+            client.delete_message_rule(&id).await?;
+            println!("Inbox rule deleted: {}", id);
+        }
+        InboxRulesCommand::Enable { id } => {
+            client.set_message_rule_enabled(&id, true).await?;
+            println!("Inbox rule enabled: {}", id);
+        }
+        InboxRulesCommand::Disable { id } => {
+            client.set_message_rule_enabled(&id, false).await?;
+            println!("Inbox rule disabled: {}", id);
+        }
+    }
+    Ok(())
+}
+
+fn load_definition(path: &Path) -> anyhow::Result<NewMessageRule> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("cannot read {}: {}", path.display(), e))?;
+    let is_toml = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("toml"));
+    let rule = if is_toml {
+        toml::from_str(&text)?
+    } else {
+        serde_json::from_str(&text)?
+    };
+    Ok(rule)
+}
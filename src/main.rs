@@ -14,13 +14,48 @@
 //!   send     - Send an email
 //!   reply    - Reply to an email
 //!   forward  - Forward an email
+//!   thread   - Show a conversation
 //!   drafts   - List drafts
+//!   scheduled - List or cancel scheduled messages
+//!   draft    - Show, edit, delete a draft and manage its attachments
 //!   events   - Calendar commands
+//!   calendars - List, create and delete calendars
+//!   agenda   - Upcoming events grouped by day
+//!   freebusy - Free/busy lookup for colleagues
+//!   find-time - Suggest meeting times
 //!   contacts - Contact commands
+//!   watch    - Watch for new mail (optionally forward to a webhook)
+//!   subscriptions - Graph change-notification subscriptions
+//!   rules    - Client-side mail rules
+//!   inbox-rules - Server-side inbox rules
+//!   ooo      - Automatic replies (out-of-office)
+//!   settings - Mailbox settings
 
-use clap::{Parser, Subcommand};
+mod agenda;
+mod attachments;
+mod availability;
+mod calendar;
+mod calendars;
+mod dates;
+mod draft;
+mod ics;
+mod inbox_rules;
+mod ooo;
+mod render;
+mod rules;
+mod schedule;
+mod settings;
+mod subscriptions;
+mod thread;
+mod watch;
+mod webhook;
+
+use std::io::IsTerminal;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use fafafa_outlook_core::{
-    DateTimeTimeZone, DraftMessage, NewCalendarEvent, NewContact, NewMessage, OutlookClient,
+    DateTimeTimeZone, DraftMessage, EventResponse, NewCalendarEvent, NewContact, NewMessage,
+    OutlookClient,
 };
 
 const DEFAULT_CLIENT_ID: &str = fafafa_outlook_core::auth::DEFAULT_CLIENT_ID;
@@ -53,6 +88,9 @@ enum Commands {
         /// Number of emails to show
         #[arg(short, long, default_value = "10")]
         limit: u32,
+        /// Group messages by conversation
+        #[arg(long)]
+        threads: bool,
     },
     /// List unread emails
     Unread {
@@ -77,6 +115,16 @@ enum Commands {
     Folders,
     /// Get current user info
     Me,
+    /// Manage automatic replies (out-of-office)
+    Ooo {
+        #[command(subcommand)]
+        command: ooo::OooCommand,
+    },
+    /// Show or update mailbox settings
+    Settings {
+        #[command(subcommand)]
+        command: settings::SettingsCommand,
+    },
     /// Send an email
     Send {
         /// Recipient email address
@@ -90,28 +138,45 @@ enum Commands {
         /// Send as HTML (default: plain text)
         #[arg(long)]
         html: bool,
+        /// Deliver later at this time (e.g. 2024-01-15T09:00, 'tomorrow 9am'; mailbox timezone if no offset)
+        #[arg(long)]
+        at: Option<String>,
+        #[command(flatten)]
+        options: SendOptions,
     },
     /// Reply to an email
     Reply {
         /// Email ID to reply to
         id: String,
         /// Reply message
-        #[arg(short, long)]
-        message: String,
+        #[arg(short, long, required_unless_present = "draft")]
+        message: Option<String>,
         /// Reply to all recipients
         #[arg(long)]
         all: bool,
+        /// Save the reply as a draft instead of sending it
+        #[arg(long)]
+        draft: bool,
+        /// Open the reply draft in $EDITOR
+        #[arg(long, requires = "draft")]
+        edit: bool,
     },
     /// Forward an email
     Forward {
         /// Email ID to forward
         id: String,
         /// Recipient email addresses (comma-separated)
-        #[arg(short, long)]
-        to: String,
+        #[arg(short, long, required_unless_present = "draft")]
+        to: Option<String>,
         /// Optional comment
         #[arg(short, long)]
         comment: Option<String>,
+        /// Save the forward as a draft instead of sending it
+        #[arg(long)]
+        draft: bool,
+        /// Open the forward draft in $EDITOR
+        #[arg(long, requires = "draft")]
+        edit: bool,
     },
     /// Delete an email
     Delete {
@@ -122,23 +187,63 @@ enum Commands {
     Get {
         /// Email ID
         id: String,
+        /// Print the body exactly as returned (no HTML rendering)
+        #[arg(long)]
+        raw: bool,
+        /// Body format to request from the server
+        #[arg(long, value_enum, default_value = "html", conflicts_with_all = ["headers", "full"])]
+        prefer: BodyFormat,
+        /// Show recipients, metadata and internet message headers instead of the body
+        #[arg(long)]
+        headers: bool,
+        /// Show recipients, metadata, internet message headers and the body
+        #[arg(long, conflicts_with = "headers")]
+        full: bool,
+    },
+    /// Show all messages in an email's conversation
+    Thread {
+        /// ID of any message in the conversation
+        id: String,
     },
     /// List attachments for an email
     Attachments {
         /// Email ID
         id: String,
     },
-    /// Download an attachment
+    /// Download an attachment, or all attachments of emails
+    #[command(group(
+        clap::ArgGroup::new("source")
+            .required(true)
+            .args(["email_id", "all", "from_search"])
+    ))]
     Download {
         /// Email ID
-        #[arg(short, long)]
-        email_id: String,
+        #[arg(short, long, requires = "attachment_id")]
+        email_id: Option<String>,
         /// Attachment ID
-        #[arg(short, long)]
-        attachment_id: String,
+        #[arg(short, long, requires = "email_id")]
+        attachment_id: Option<String>,
         /// Output file path
-        #[arg(short, long)]
-        output: String,
+        #[arg(short, long, requires = "email_id", conflicts_with = "stdout")]
+        output: Option<String>,
+        /// Write the attachment to stdout instead of a file
+        #[arg(long, requires = "attachment_id")]
+        stdout: bool,
+        /// Download every attachment of this email
+        #[arg(long, value_name = "EMAIL_ID")]
+        all: Option<String>,
+        /// Download every attachment of emails matching this subject search
+        #[arg(long, value_name = "QUERY")]
+        from_search: Option<String>,
+        /// Directory for --all / --from-search downloads
+        #[arg(short, long, default_value = ".")]
+        dir: String,
+        /// Skip inline attachments (e.g. embedded images)
+        #[arg(long)]
+        skip_inline: bool,
+        /// Number of search results to download from
+        #[arg(short, long, default_value = "10")]
+        limit: u32,
     },
     /// Poll for new messages
     Poll {
@@ -150,6 +255,50 @@ enum Commands {
     },
     /// Get unread message count
     UnreadCount,
+    /// Apply client-side mail rules
+    Rules {
+        #[command(subcommand)]
+        command: rules::RulesCommand,
+    },
+    /// Manage server-side inbox rules
+    InboxRules {
+        #[command(subcommand)]
+        command: inbox_rules::InboxRulesCommand,
+    },
+    /// Watch for new messages, optionally forwarding them to a webhook
+    Watch {
+        /// ISO 8601 datetime to start from (default: now)
+        #[arg(long)]
+        since: Option<String>,
+        /// Poll interval in seconds
+        #[arg(short, long, default_value = "30")]
+        interval: u64,
+        /// Maximum messages fetched per poll
+        #[arg(short, long, default_value = "50")]
+        limit: u32,
+        /// Webhook URL to POST new message events to
+        #[arg(short, long)]
+        webhook: Option<String>,
+        /// Secret used to HMAC-SHA256 sign webhook payloads
+        #[arg(long, env = "OUTLOOK_WEBHOOK_SECRET", hide_env_values = true)]
+        secret: Option<String>,
+        /// Include the message body in webhook payloads
+        #[arg(long)]
+        include_body: bool,
+        /// Delivery retries before giving up on an event
+        #[arg(long, default_value = "5")]
+        retries: u32,
+        /// File to append undeliverable events to (JSON lines; without it they are dropped)
+        #[arg(long)]
+        dead_letter: Option<String>,
+    },
+
+    // ==================== Subscriptions ====================
+    /// Manage Graph change-notification subscriptions
+    Subscriptions {
+        #[command(subcommand)]
+        command: subscriptions::SubscriptionCommand,
+    },
 
     // ==================== Drafts ====================
     /// List draft emails
@@ -172,45 +321,97 @@ enum Commands {
         /// Send as HTML
         #[arg(long)]
         html: bool,
+        #[command(flatten)]
+        options: SendOptions,
     },
     /// Send a draft email
     SendDraft {
         /// Draft ID
         id: String,
+        /// Deliver later at this time (e.g. 2024-01-15T09:00, 'tomorrow 9am'; mailbox timezone if no offset)
+        #[arg(long)]
+        at: Option<String>,
+    },
+    /// List or cancel scheduled (deferred) messages
+    Scheduled {
+        #[command(subcommand)]
+        command: Option<schedule::ScheduledCommand>,
+    },
+    /// Manage a draft
+    Draft {
+        #[command(subcommand)]
+        command: draft::DraftCommand,
     },
 
     // ==================== Calendar ====================
     /// List calendar events
+    #[command(args_conflicts_with_subcommands = true)]
     Events {
         /// Number of events to show
         #[arg(short, long, default_value = "10")]
         limit: u32,
-        /// Start date (ISO 8601)
+        /// Start of the range (ISO 8601 or e.g. today, 'next monday'; default: today)
         #[arg(long)]
         start: Option<String>,
-        /// End date (ISO 8601)
+        /// End of the range (default: one week after the start)
         #[arg(long)]
         end: Option<String>,
+        /// Expand recurring series into individual occurrences
+        #[arg(long)]
+        expand: bool,
+        /// Only today's events (implies --expand)
+        #[arg(long, conflicts_with_all = ["week", "start", "end"])]
+        today: bool,
+        /// Only this week's events (implies --expand)
+        #[arg(long, conflicts_with_all = ["start", "end"])]
+        week: bool,
+        /// Window for --expand when --start/--end are not given
+        #[arg(long, value_enum, default_value = "week")]
+        window: calendar::Window,
+        /// Calendar name or ID (default: your default calendar)
+        #[arg(long)]
+        calendar: Option<String>,
+        #[command(subcommand)]
+        command: Option<calendar::EventsCommand>,
+    },
+    /// Manage calendars
+    Calendars {
+        #[command(subcommand)]
+        command: calendars::CalendarsCommand,
     },
+    /// List, edit or cancel occurrences of a recurring event
+    Instances(calendar::InstancesArgs),
+    /// Show upcoming events grouped by day, in the mailbox or local timezone
+    Agenda(agenda::AgendaArgs),
+    /// Show when people are busy (getSchedule)
+    Freebusy(availability::FreeBusyArgs),
+    /// Suggest meeting times that suit all attendees
+    FindTime(availability::FindTimeArgs),
     /// Get event details
     Event {
         /// Event ID
         id: String,
+        /// Calendar name or ID the event belongs to
+        #[arg(long)]
+        calendar: Option<String>,
     },
     /// Create a calendar event
     CreateEvent {
         /// Event subject
         #[arg(short, long)]
         subject: String,
-        /// Start datetime (ISO 8601)
+        /// Start (ISO 8601 or e.g. 'tomorrow 10:00', 'next monday 9am', +2h)
         #[arg(long)]
         start: String,
-        /// End datetime (ISO 8601)
+        /// End (same formats as --start)
+        #[arg(long, required_unless_present_any = ["duration", "all_day"])]
+        end: Option<String>,
+        /// Duration instead of --end (e.g. 45m, 1h30m; all-day events default to 1d)
+        #[arg(long, conflicts_with = "end")]
+        duration: Option<String>,
+        /// Timezone (default: mailbox timezone)
         #[arg(long)]
-        end: String,
-        /// Timezone (default: UTC)
-        #[arg(long, default_value = "UTC")]
-        timezone: String,
+        timezone: Option<String>,
         /// Location
         #[arg(short, long)]
         location: Option<String>,
@@ -223,11 +424,21 @@ enum Commands {
         /// Online meeting
         #[arg(long)]
         online: bool,
+        #[command(flatten)]
+        recurrence: calendar::RecurrenceArgs,
+        /// Calendar name or ID to create the event in
+        #[arg(long)]
+        calendar: Option<String>,
     },
+    /// Update a calendar event (only the given fields change)
+    UpdateEvent(calendar::UpdateEventArgs),
     /// Delete a calendar event
     DeleteEvent {
         /// Event ID
         id: String,
+        /// Calendar name or ID the event belongs to
+        #[arg(long)]
+        calendar: Option<String>,
     },
     /// Accept a calendar event invitation
     AcceptEvent {
@@ -236,6 +447,9 @@ enum Commands {
         /// Optional comment
         #[arg(short, long)]
         comment: Option<String>,
+        /// Update your response without notifying the organizer
+        #[arg(long)]
+        no_send_response: bool,
     },
     /// Decline a calendar event invitation
     DeclineEvent {
@@ -244,6 +458,24 @@ enum Commands {
         /// Optional comment
         #[arg(short, long)]
         comment: Option<String>,
+        /// Update your response without notifying the organizer
+        #[arg(long)]
+        no_send_response: bool,
+        #[command(flatten)]
+        propose: calendar::ProposeTime,
+    },
+    /// Tentatively accept a calendar event invitation
+    TentativeEvent {
+        /// Event ID
+        id: String,
+        /// Optional comment
+        #[arg(short, long)]
+        comment: Option<String>,
+        /// Update your response without notifying the organizer
+        #[arg(long)]
+        no_send_response: bool,
+        #[command(flatten)]
+        propose: calendar::ProposeTime,
     },
 
     // ==================== Contacts ====================
@@ -289,6 +521,57 @@ enum Commands {
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum BodyFormat {
+    Html,
+    Text,
+}
+
+/// Delivery options shared by `send` and `create-draft`
+#[derive(Args)]
+struct SendOptions {
+    /// Message importance
+    #[arg(long, value_enum)]
+    importance: Option<Importance>,
+    /// Ask recipients for a read receipt
+    #[arg(long)]
+    request_read_receipt: bool,
+    /// Ask for a delivery receipt
+    #[arg(long)]
+    request_delivery_receipt: bool,
+    /// Custom internet header, e.g. "X-Ticket:1234" (repeatable; name must start with X-)
+    #[arg(long = "header", value_name = "NAME:VALUE", value_parser = parse_header)]
+    headers: Vec<(String, String)>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Importance {
+    Low,
+    Normal,
+    High,
+}
+
+impl Importance {
+    fn as_graph(self) -> &'static str {
+        match self {
+            Importance::Low => "low",
+            Importance::Normal => "normal",
+            Importance::High => "high",
+        }
+    }
+}
+
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected NAME:VALUE, got '{}'", s))?;
+    let name = name.trim();
+    if !name.to_ascii_lowercase().starts_with("x-") {
+        return Err(format!("custom header '{}' must start with X-", name));
+    }
+    Ok((name.to_string(), value.trim().to_string()))
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Load .env file
@@ -299,9 +582,13 @@ async fn main() -> anyhow::Result<()> {
     let client = OutlookClient::with_credentials(&cli.client_id, &cli.token).await?;
 
     match cli.command {
-        Commands::Inbox { limit } => {
+        Commands::Inbox { limit, threads } => {
             let messages = client.inbox(limit).await?;
-            print_messages(&messages);
+            if threads {
+                thread::print_threads(&messages);
+            } else {
+                print_messages(&messages);
+            }
         }
         Commands::Unread { limit } => {
             let messages = client.unread(limit).await?;
@@ -343,40 +630,125 @@ async fn main() -> anyhow::Result<()> {
             println!("User: {}", user.display_name.unwrap_or_default());
             println!("Email: {}", user.mail.unwrap_or_default());
         }
+        Commands::Ooo { command } => {
+            ooo::run(&client, command).await?;
+        }
+        Commands::Settings { command } => {
+            settings::run(&client, command).await?;
+        }
         Commands::Send {
             to,
             subject,
             body,
             html,
+            at,
+            options,
         } => {
-            let message = if html {
+            let mut message = if html {
                 NewMessage::html(&to, &subject, &body)
             } else {
                 NewMessage::text(&to, &subject, &body)
             };
+            if let Some(importance) = options.importance {
+                message = message.importance(importance.as_graph());
+            }
+            if options.request_read_receipt {
+                message = message.request_read_receipt();
+            }
+            if options.request_delivery_receipt {
+                message = message.request_delivery_receipt();
+            }
+            for (name, value) in &options.headers {
+                message = message.header(name, value);
+            }
+            let at = match at {
+                Some(at) => Some(schedule::parse_send_time(&client, &at).await?),
+                None => None,
+            };
+            if let Some(at) = &at {
+                message = message.deferred_send(at.at);
+            }
             client.send_mail(message).await?;
-            println!("Email sent to: {}", to);
+            match at {
+                Some(at) => println!("Email to {} scheduled for {}", to, at.display),
+                None => println!("Email sent to: {}", to),
+            }
         }
-        Commands::Reply { id, message, all } => {
-            if all {
-                client.reply_all(&id, &message).await?;
-                println!("Replied all to: {}", id);
+        Commands::Reply {
+            id,
+            message,
+            all,
+            draft,
+            edit,
+        } => {
+            if draft {
+                let created = if all {
+                    client
+                        .create_reply_all_draft(&id, message.as_deref())
+                        .await?
+                } else {
+                    client.create_reply_draft(&id, message.as_deref()).await?
+                };
+                println!("Reply draft created: {}", created.id);
+                if edit {
+                    draft::edit(&client, &created.id).await?;
+                }
             } else {
-                client.reply(&id, &message).await?;
-                println!("Replied to: {}", id);
+                let message = message.unwrap_or_default();
+                if all {
+                    client.reply_all(&id, &message).await?;
+                    println!("Replied all to: {}", id);
+                } else {
+                    client.reply(&id, &message).await?;
+                    println!("Replied to: {}", id);
+                }
             }
         }
-        Commands::Forward { id, to, comment } => {
-            let recipients: Vec<&str> = to.split(',').map(|s| s.trim()).collect();
-            client.forward(&id, &recipients, comment.as_deref()).await?;
-            println!("Forwarded to: {}", to);
+        Commands::Forward {
+            id,
+            to,
+            comment,
+            draft,
+            edit,
+        } => {
+            let to = to.unwrap_or_default();
+            let recipients: Vec<&str> = to
+                .split(',')
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .collect();
+            if draft {
+                let created = client
+                    .create_forward_draft(&id, &recipients, comment.as_deref())
+                    .await?;
+                println!("Forward draft created: {}", created.id);
+                if edit {
+                    draft::edit(&client, &created.id).await?;
+                }
+            } else {
+                client.forward(&id, &recipients, comment.as_deref()).await?;
+                println!("Forwarded to: {}", to);
+            }
         }
         Commands::Delete { id } => {
             client.delete_message(&id).await?;
             println!("Deleted: {}", id);
         }
-        Commands::Get { id } => {
-            let msg = client.get_message_with_body(&id).await?;
+        Commands::Get {
+            id,
+            raw,
+            prefer,
+            headers,
+            full,
+        } => {
+            let msg = if headers || full {
+                client.get_message_full(&id).await?
+            } else {
+                match prefer {
+                    BodyFormat::Html => client.get_message_with_body(&id).await?,
+                    BodyFormat::Text => client.get_message_with_text_body(&id).await?,
+                }
+            };
             println!(
                 "Subject: {}",
                 msg.subject.as_deref().unwrap_or("(no subject)")
@@ -402,11 +774,66 @@ async fn main() -> anyhow::Result<()> {
                     "No"
                 }
             );
-            println!();
-            if let Some(body) = msg.body {
-                println!("{}", body.content);
+            if headers || full {
+                print_recipients("To", msg.to_recipients.as_deref());
+                print_recipients("Cc", msg.cc_recipients.as_deref());
+                print_recipients("Bcc", msg.bcc_recipients.as_deref());
+                print_recipients("Reply-To", msg.reply_to.as_deref());
+                if let Some(importance) = &msg.importance {
+                    println!("Importance: {}", importance);
+                }
+                if let Some(categories) = msg.categories.as_ref().filter(|c| !c.is_empty()) {
+                    println!("Categories: {}", categories.join(", "));
+                }
+                if let Some(status) = msg.flag.as_ref().and_then(|f| f.flag_status.as_deref()) {
+                    println!("Flag: {}", status);
+                }
+                if let Some(conversation_id) = &msg.conversation_id {
+                    println!("Conversation ID: {}", conversation_id);
+                }
+                if let Some(message_id) = &msg.internet_message_id {
+                    println!("Message-ID: {}", message_id);
+                }
+                if msg.has_attachments.unwrap_or(false) {
+                    let attachments = client.list_attachments(&id).await?;
+                    let names: Vec<String> = attachments
+                        .iter()
+                        .map(|att| match att.size {
+                            Some(size) => format!("{} ({} bytes)", att.name, size),
+                            None => att.name.clone(),
+                        })
+                        .collect();
+                    println!("Attachments: {}", names.join(", "));
+                }
+                if let Some(link) = &msg.web_link {
+                    println!("Web link: {}", link);
+                }
+                println!();
+                println!("Internet message headers:");
+                match &msg.internet_message_headers {
+                    Some(list) if !list.is_empty() => {
+                        for header in list {
+                            println!("  {}: {}", header.name, header.value);
+                        }
+                    }
+                    _ => println!("  (none)"),
+                }
+            }
+            if !headers {
+                println!();
+                if let Some(body) = msg.body {
+                    if !raw && body.content_type.eq_ignore_ascii_case("html") {
+                        let color = std::io::stdout().is_terminal();
+                        println!("{}", render::html_to_text(&body.content, color));
+                    } else {
+                        println!("{}", body.content);
+                    }
+                }
             }
         }
+        Commands::Thread { id } => {
+            thread::show(&client, &id).await?;
+        }
         Commands::Attachments { id } => {
             let attachments = client.list_attachments(&id).await?;
             if attachments.is_empty() {
@@ -426,12 +853,45 @@ async fn main() -> anyhow::Result<()> {
             email_id,
             attachment_id,
             output,
+            stdout,
+            all,
+            from_search,
+            dir,
+            skip_inline,
+            limit,
         } => {
-            let bytes = client
-                .download_attachment(&email_id, &attachment_id)
-                .await?;
-            std::fs::write(&output, &bytes)?;
-            println!("Downloaded {} bytes to: {}", bytes.len(), output);
+            let dir = std::path::Path::new(&dir);
+            if let Some(email_id) = all {
+                let saved = attachments::download_all(&client, &email_id, dir, skip_inline).await?;
+                println!("Downloaded {} files to: {}", saved.len(), dir.display());
+            } else if let Some(query) = from_search {
+                let messages = client.search_by_subject(&query, limit).await?;
+                let mut total = 0;
+                for msg in messages
+                    .iter()
+                    .filter(|m| m.has_attachments.unwrap_or(false))
+                {
+                    println!("{}", msg.subject.as_deref().unwrap_or("(no subject)"));
+                    total += attachments::download_all(&client, &msg.id, dir, skip_inline)
+                        .await?
+                        .len();
+                }
+                println!("Downloaded {} files to: {}", total, dir.display());
+            } else if let (Some(email_id), Some(attachment_id)) = (email_id, attachment_id) {
+                if stdout {
+                    let done =
+                        attachments::save_to_stdout(&client, &email_id, &attachment_id).await?;
+                    eprintln!("SHA-256: {}", done.sha256);
+                } else {
+                    let output = output
+                        .ok_or_else(|| anyhow::anyhow!("--output or --stdout is required"))?;
+                    let path = std::path::Path::new(&output);
+                    let done =
+                        attachments::save_to_file(&client, &email_id, &attachment_id, path).await?;
+                    println!("Downloaded {} bytes to: {}", done.bytes, output);
+                    println!("SHA-256: {}", done.sha256);
+                }
+            }
         }
         Commands::Poll { since, limit } => {
             let messages = client.poll_new_messages(&since, limit).await?;
@@ -446,6 +906,44 @@ async fn main() -> anyhow::Result<()> {
             let count = client.unread_count().await?;
             println!("Unread messages: {}", count);
         }
+        Commands::Rules { command } => {
+            rules::run(&client, command).await?;
+        }
+        Commands::InboxRules { command } => {
+            inbox_rules::run(&client, command).await?;
+        }
+        Commands::Watch {
+            since,
+            interval,
+            limit,
+            webhook,
+            secret,
+            include_body,
+            retries,
+            dead_letter,
+        } => {
+            let forwarder = webhook.map(|url| {
+                webhook::WebhookForwarder::new(webhook::WebhookConfig {
+                    url,
+                    secret,
+                    max_retries: retries,
+                    retry_delay: webhook::RETRY_DELAY,
+                    dead_letter: dead_letter.map(Into::into),
+                })
+            });
+            let options = watch::WatchOptions {
+                since,
+                interval,
+                limit,
+                include_body,
+            };
+            watch::run(&client, options, forwarder).await?;
+        }
+
+        // ==================== Subscriptions ====================
+        Commands::Subscriptions { command } => {
+            subscriptions::run(&client, command).await?;
+        }
 
         // ==================== Drafts ====================
         Commands::Drafts { limit } => {
@@ -462,8 +960,21 @@ async fn main() -> anyhow::Result<()> {
             body,
             to,
             html,
+            options,
         } => {
             let mut draft = DraftMessage::new();
+            if let Some(importance) = options.importance {
+                draft = draft.importance(importance.as_graph());
+            }
+            if options.request_read_receipt {
+                draft = draft.request_read_receipt();
+            }
+            if options.request_delivery_receipt {
+                draft = draft.request_delivery_receipt();
+            }
+            for (name, value) in &options.headers {
+                draft = draft.header(name, value);
+            }
             if let Some(s) = subject {
                 draft = draft.subject(s);
             }
@@ -481,36 +992,82 @@ async fn main() -> anyhow::Result<()> {
             let created = client.create_draft(draft).await?;
             println!("Draft created: {}", created.id);
         }
-        Commands::SendDraft { id } => {
-            client.send_draft(&id).await?;
-            println!("Draft sent: {}", id);
+        Commands::SendDraft { id, at } => {
+            if let Some(at) = at {
+                let at = schedule::parse_send_time(&client, &at).await?;
+                client.set_deferred_send(&id, at.at).await?;
+                client.send_draft(&id).await?;
+                println!("Draft {} scheduled for {}", id, at.display);
+            } else {
+                client.send_draft(&id).await?;
+                println!("Draft sent: {}", id);
+            }
+        }
+        Commands::Scheduled { command } => {
+            let command = command.unwrap_or(schedule::ScheduledCommand::List { limit: 20 });
+            schedule::run(&client, command).await?;
+        }
+        Commands::Draft { command } => {
+            draft::run(&client, command).await?;
         }
 
         // ==================== Calendar ====================
-        Commands::Events { limit, start, end } => {
-            let events = if let (Some(s), Some(e)) = (start, end) {
-                client.list_events_range(&s, &e, limit).await?
+        Commands::Events {
+            limit,
+            start,
+            end,
+            expand,
+            window,
+            today,
+            week,
+            calendar,
+            command,
+        } => {
+            if let Some(calendar::EventsCommand::Export(args)) = command {
+                return ics::export(&client, args).await;
+            }
+            let calendar = calendars::resolve(&client, calendar.as_deref()).await?;
+            let window = match (today, week) {
+                (true, _) => calendar::Window::Today,
+                (_, true) => calendar::Window::Week,
+                _ => window,
+            };
+            // The calendar view returns occurrences instead of series masters
+            let events = if expand || today || week || start.is_some() || end.is_some() {
+                let clock = dates::Clock::new(&settings::mailbox_timezone(&client).await?)?;
+                let (s, e) =
+                    calendar::view_range(&clock, start.as_deref(), end.as_deref(), window)?;
+                client
+                    .list_events_range_in(&calendar, &s, &e, limit)
+                    .await?
             } else {
-                client.list_events(limit).await?
+                client.list_events_in(&calendar, limit).await?
             };
             if events.is_empty() {
                 println!("No events");
             } else {
                 println!("Calendar Events:");
-                for event in events {
-                    let start_str = event
-                        .start
-                        .as_ref()
-                        .map(|d| d.date_time.as_str())
-                        .unwrap_or("?");
-                    let subject = event.subject.as_deref().unwrap_or("(no subject)");
-                    println!("  {} - {}", start_str, subject);
-                    println!("    ID: {}", event.id);
-                }
+                calendar::print_events(&events);
             }
         }
-        Commands::Event { id } => {
-            let event = client.get_event(&id).await?;
+        Commands::Calendars { command } => {
+            calendars::run(&client, command).await?;
+        }
+        Commands::Agenda(args) => {
+            agenda::show(&client, args).await?;
+        }
+        Commands::Freebusy(args) => {
+            availability::freebusy(&client, args).await?;
+        }
+        Commands::FindTime(args) => {
+            availability::find_time(&client, args).await?;
+        }
+        Commands::Instances(args) => {
+            calendar::instances(&client, args).await?;
+        }
+        Commands::Event { id, calendar } => {
+            let calendar = calendars::resolve(&client, calendar.as_deref()).await?;
+            let event = client.get_event_in(&calendar, &id).await?;
             println!(
                 "Subject: {}",
                 event.subject.as_deref().unwrap_or("(no subject)")
@@ -521,6 +1078,9 @@ async fn main() -> anyhow::Result<()> {
             if let Some(end) = event.end {
                 println!("End: {} ({})", end.date_time, end.time_zone);
             }
+            if let Some(recurrence) = &event.recurrence {
+                println!("Repeats: {}", calendar::describe_recurrence(recurrence));
+            }
             if let Some(loc) = event.location {
                 if let Some(name) = loc.display_name {
                     println!("Location: {}", name);
@@ -537,12 +1097,33 @@ async fn main() -> anyhow::Result<()> {
             subject,
             start,
             end,
+            duration,
             timezone,
             location,
             attendees,
             all_day,
             online,
+            recurrence,
+            calendar,
         } => {
+            let calendar = calendars::resolve(&client, calendar.as_deref()).await?;
+            let timezone = match timezone {
+                Some(tz) => tz,
+                None => settings::mailbox_timezone(&client).await?,
+            };
+            let clock = dates::Clock::new(&timezone)?;
+            let start_at = clock.parse(&start)?;
+            let end_at = match (end, duration) {
+                (Some(end), _) => clock.parse(&end)?,
+                (None, Some(duration)) => start_at + dates::parse_duration(&duration)?,
+                (None, None) => start_at + chrono::Duration::days(1),
+            };
+            if end_at <= start_at {
+                anyhow::bail!("end must be after start");
+            }
+            let start = dates::format(start_at);
+            let end = dates::format(end_at);
+            let recurrence = recurrence.build(&start, &timezone)?;
             let start_dt = DateTimeTimeZone::new(&start, &timezone);
             let end_dt = DateTimeTimeZone::new(&end, &timezone);
             let mut event = NewCalendarEvent::new(&subject, start_dt, end_dt);
@@ -559,21 +1140,49 @@ async fn main() -> anyhow::Result<()> {
             if online {
                 event = event.online_meeting();
             }
-            let created = client.create_event(event).await?;
+            if let Some(recurrence) = recurrence {
+                event = event.recurrence(recurrence);
+            }
+            let created = client.create_event_in(&calendar, event).await?;
             println!("Event created: {}", created.id);
         }
-        Commands::DeleteEvent { id } => {
-            client.delete_event(&id).await?;
+        Commands::UpdateEvent(args) => {
+            calendar::update_event(&client, args).await?;
+        }
+        Commands::DeleteEvent { id, calendar } => {
+            let calendar = calendars::resolve(&client, calendar.as_deref()).await?;
+            client.delete_event_in(&calendar, &id).await?;
             println!("Event deleted: {}", id);
         }
-        Commands::AcceptEvent { id, comment } => {
-            client.accept_event(&id, comment.as_deref()).await?;
+        Commands::AcceptEvent {
+            id,
+            comment,
+            no_send_response,
+        } => {
+            let response = EventResponse::accept().send_response(!no_send_response);
+            calendar::respond(&client, &id, response, comment, None).await?;
             println!("Event accepted: {}", id);
         }
-        Commands::DeclineEvent { id, comment } => {
-            client.decline_event(&id, comment.as_deref()).await?;
+        Commands::DeclineEvent {
+            id,
+            comment,
+            no_send_response,
+            propose,
+        } => {
+            let response = EventResponse::decline().send_response(!no_send_response);
+            calendar::respond(&client, &id, response, comment, Some(propose)).await?;
             println!("Event declined: {}", id);
         }
+        Commands::TentativeEvent {
+            id,
+            comment,
+            no_send_response,
+            propose,
+        } => {
+            let response = EventResponse::tentative().send_response(!no_send_response);
+            calendar::respond(&client, &id, response, comment, Some(propose)).await?;
+            println!("Event tentatively accepted: {}", id);
+        }
 
         // ==================== Contacts ====================
         Commands::Contacts { limit, search } => {
@@ -656,6 +1265,20 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+fn print_recipients(label: &str, recipients: Option<&[fafafa_outlook_core::Recipient]>) {
+    let Some(recipients) = recipients.filter(|r| !r.is_empty()) else {
+        return;
+    };
+    let list: Vec<String> = recipients
+        .iter()
+        .map(|r| match r.email_address.name.as_deref() {
+            Some(name) if !name.is_empty() => format!("{} <{}>", name, r.email_address.address),
+            _ => r.email_address.address.clone(),
+        })
+        .collect();
+    println!("{}: {}", label, list.join(", "));
+}
+
 fn print_messages(messages: &[fafafa_outlook_core::Message]) {
     if messages.is_empty() {
         println!("(no messages)");
@@ -0,0 +1,151 @@
+//! Automatic replies (out-of-office)
+
+use clap::{Args, Subcommand, ValueEnum};
+use fafafa_outlook_core::{AutomaticRepliesSetting, DateTimeTimeZone, OutlookClient};
+
+use crate::dates::{self, Clock};
+use crate::settings;
+
+#[derive(Subcommand)]
+pub enum OooCommand {
+    /// Show automatic replies status
+    Status,
+    /// Turn on automatic replies
+    Enable(EnableArgs),
+    /// Turn off automatic replies
+    Disable,
+}
+
+#[derive(Args)]
+pub struct EnableArgs {
+    /// Reply sent inside the organization
+    #[arg(long, conflicts_with = "internal_file")]
+    internal: Option<String>,
+    /// Read the internal reply from a file
+    #[arg(long)]
+    internal_file: Option<String>,
+    /// Reply sent outside the organization (default: same as internal)
+    #[arg(long, conflicts_with = "external_file")]
+    external: Option<String>,
+    /// Read the external reply from a file
+    #[arg(long)]
+    external_file: Option<String>,
+    /// Scheduled start (e.g. 2024-07-01T09:00, 'next monday 9am'); requires --end
+    #[arg(long, requires = "end")]
+    start: Option<String>,
+    /// Scheduled end; requires --start
+    #[arg(long, requires = "start")]
+    end: Option<String>,
+    /// Timezone for --start/--end (default: mailbox timezone)
+    #[arg(long)]
+    timezone: Option<String>,
+    /// Who outside the organization receives the external reply
+    #[arg(long, value_enum, default_value = "all")]
+    audience: Audience,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Audience {
+    /// No external senders
+    None,
+    /// Only senders in your contacts
+    Contacts,
+    /// All external senders
+    All,
+}
+
+impl Audience {
+    fn as_graph(self) -> &'static str {
+        match self {
+            Audience::None => "none",
+            Audience::Contacts => "contactsOnly",
+            Audience::All => "all",
+        }
+    }
+}
+
+/// Use inline text if given, otherwise the file contents
+fn message_text(text: Option<String>, file: Option<String>) -> anyhow::Result<Option<String>> {
+    match (text, file) {
+        (Some(t), _) => Ok(Some(t)),
+        (None, Some(path)) => std::fs::read_to_string(&path)
+            .map(Some)
+            .map_err(|e| anyhow::anyhow!("cannot read {}: {}", path, e)),
+        (None, None) => Ok(None),
+    }
+}
+
+pub async fn run(client: &OutlookClient, command: OooCommand) -> anyhow::Result<()> {
+    match command {
+        OooCommand::Status => {
+            let settings = client.get_mailbox_settings().await?;
+            let Some(ooo) = settings.automatic_replies_setting else {
+                println!("Automatic replies: disabled");
+                return Ok(());
+            };
+            println!(
+                "Automatic replies: {}",
+                ooo.status.as_deref().unwrap_or("disabled")
+            );
+            if ooo.status.as_deref() == Some("scheduled") {
+                if let Some(start) = &ooo.scheduled_start_date_time {
+                    println!("Start: {} ({})", start.date_time, start.time_zone);
+                }
+                if let Some(end) = &ooo.scheduled_end_date_time {
+                    println!("End: {} ({})", end.date_time, end.time_zone);
+                }
+            }
+            if let Some(audience) = &ooo.external_audience {
+                println!("External audience: {}", audience);
+            }
+            if let Some(internal) = &ooo.internal_reply_message {
+                println!();
+                println!("Internal reply:");
+                println!("{}", internal);
+            }
+            if let Some(external) = &ooo.external_reply_message {
+                println!();
+                println!("External reply:");
+                println!("{}", external);
+            }
+        }
+        OooCommand::Enable(args) => {
+            let internal = message_text(args.internal, args.internal_file)?
+                .ok_or_else(|| anyhow::anyhow!("--internal or --internal-file is required"))?;
+            let external = message_text(args.external, args.external_file)?
+                .unwrap_or_else(|| internal.clone());
+
+            let setting = match (args.start, args.end) {
+                (Some(start), Some(end)) => {
+                    let timezone = match args.timezone {
+                        Some(tz) => tz,
+                        None => settings::mailbox_timezone(client).await?,
+                    };
+                    let clock = Clock::new(&timezone)?;
+                    let start = clock.parse(&start)?;
+                    let end = clock.parse(&end)?;
+                    if end <= start {
+                        anyhow::bail!("--end must be after --start");
+                    }
+                    AutomaticRepliesSetting::scheduled(
+                        DateTimeTimeZone::new(&dates::format(start), &timezone),
+                        DateTimeTimeZone::new(&dates::format(end), &timezone),
+                    )
+                }
+                _ => AutomaticRepliesSetting::always_enabled(),
+            }
+            .internal_message(internal)
+            .external_message(external)
+            .external_audience(args.audience.as_graph());
+            client.set_automatic_replies(setting).await?;
+            println!("Automatic replies enabled");
+        }
+        OooCommand::Disable => {
+            client
+                .set_automatic_replies(AutomaticRepliesSetting::disabled())
+                .await?;
+            println!("Automatic replies disabled");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,602 @@
+//! HTML to terminal text rendering for message bodies
+//!
+//! A small, forgiving renderer for the HTML Outlook produces: block tags
+//! become line breaks, links become numbered footnotes, tables are laid out
+//! in aligned columns, and quoted replies are prefixed with `>` (and dimmed
+//! when color is enabled).
+
+use unicode_width::UnicodeWidthStr;
+
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Elements whose content is never shown
+const SKIPPED: [&str; 4] = ["head", "script", "style", "title"];
+
+/// Elements that start and end on their own line
+const BLOCKS: [&str; 18] = [
+    "p",
+    "div",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "li",
+    "tr",
+    "table",
+    "blockquote",
+    "pre",
+    "hr",
+    "section",
+    "article",
+];
+
+/// Render an HTML body as readable text
+pub fn html_to_text(html: &str, color: bool) -> String {
+    let mut r = Renderer::default();
+    r.run(html);
+    r.finish(color)
+}
+
+#[derive(Default)]
+struct Line {
+    quote: usize,
+    text: String,
+}
+
+/// Widest cell that still gets a column layout; wider tables are layout
+/// tables and their cells are rendered as paragraphs
+const MAX_CELL_WIDTH: usize = 60;
+
+#[derive(Default)]
+struct Table {
+    rows: Vec<Vec<String>>,
+    /// Cell being filled; block breaks inside it are kept as `\n`
+    cell: Option<String>,
+}
+
+#[derive(Default)]
+struct Renderer {
+    lines: Vec<Line>,
+    links: Vec<String>,
+    /// Open `<a>` hrefs, `None` for anchors that get no footnote
+    anchors: Vec<Option<String>>,
+    /// Open lists, `Some(n)` for ordered lists
+    lists: Vec<Option<u32>>,
+    /// Open divs, `true` for divs that start quoted content
+    divs: Vec<bool>,
+    quote: usize,
+    skip: usize,
+    pre: usize,
+    tables: Vec<Table>,
+    /// Set after Outlook's reply separator; everything following is history
+    history: bool,
+    space: bool,
+}
+
+impl Renderer {
+    fn run(&mut self, html: &str) {
+        let mut rest = html;
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("<!--") {
+                rest = after.find("-->").map(|i| &after[i + 3..]).unwrap_or("");
+            } else if starts_tag(rest) {
+                match tag_end(rest) {
+                    Some(end) => {
+                        self.tag(&rest[1..end - 1]);
+                        rest = &rest[end..];
+                    }
+                    // Unterminated tag: keep the remainder as text
+                    None => {
+                        if self.skip == 0 {
+                            self.text(&decode_entities(rest));
+                        }
+                        rest = "";
+                    }
+                }
+            } else {
+                // A `<` that starts no tag is literal text
+                let end = rest
+                    .char_indices()
+                    .skip(1)
+                    .find(|(i, _)| starts_tag(&rest[*i..]))
+                    .map_or(rest.len(), |(i, _)| i);
+                if self.skip == 0 {
+                    self.text(&decode_entities(&rest[..end]));
+                }
+                rest = &rest[end..];
+            }
+        }
+    }
+
+    fn tag(&mut self, raw: &str) {
+        let closing = raw.starts_with('/');
+        let raw = raw.trim_start_matches('/').trim_end_matches('/');
+        let name_end = raw.find(|c: char| c.is_whitespace()).unwrap_or(raw.len());
+        let name = raw[..name_end].to_ascii_lowercase();
+        let attrs = &raw[name_end..];
+
+        if SKIPPED.contains(&name.as_str()) {
+            if closing {
+                self.skip = self.skip.saturating_sub(1);
+            } else {
+                self.skip += 1;
+            }
+            return;
+        }
+        if self.skip > 0 {
+            return;
+        }
+
+        if BLOCKS.contains(&name.as_str()) || name == "br" {
+            match self.table_cell() {
+                Some(cell) if !cell.is_empty() && !cell.ends_with('\n') => cell.push('\n'),
+                Some(_) => {}
+                None => self.newline(),
+            }
+        }
+
+        match (name.as_str(), closing) {
+            ("hr", false) => {
+                self.text_raw(&"─".repeat(40));
+                self.newline();
+            }
+            ("p", true) | ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => self.blank_line(),
+            ("blockquote", false) => self.quote += 1,
+            ("blockquote", true) => self.quote = self.quote.saturating_sub(1),
+            ("div", false) => {
+                let id = attr(attrs, "id").unwrap_or_default();
+                let class = attr(attrs, "class").unwrap_or_default();
+                if id == "divRplyFwdMsg" || id == "appendonsend" {
+                    // Outlook does not nest the quoted history; it follows this div
+                    if !self.history {
+                        self.history = true;
+                        self.quote += 1;
+                    }
+                    self.divs.push(false);
+                } else if class.split_whitespace().any(|c| c == "gmail_quote") {
+                    self.quote += 1;
+                    self.divs.push(true);
+                } else {
+                    self.divs.push(false);
+                }
+            }
+            ("div", true) => {
+                let opened_quote = self.divs.pop() == Some(true);
+                self.quote = self.quote.saturating_sub(usize::from(opened_quote));
+            }
+            ("pre", false) => self.pre += 1,
+            ("pre", true) => self.pre = self.pre.saturating_sub(1),
+            ("ul", false) => self.lists.push(None),
+            ("ol", false) => self.lists.push(Some(0)),
+            ("ul" | "ol", true) => {
+                self.lists.pop();
+            }
+            ("li", false) => {
+                let indent = "  ".repeat(self.lists.len().saturating_sub(1));
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}{}. ", indent, n)
+                    }
+                    _ => format!("{}• ", indent),
+                };
+                self.text_raw(&bullet);
+            }
+            ("a", false) => {
+                let href = attr(attrs, "href").filter(|h| !h.is_empty() && !h.starts_with('#'));
+                self.anchors.push(href);
+            }
+            ("a", true) => {
+                if let Some(Some(href)) = self.anchors.pop() {
+                    let shown = self.current_text().trim_end();
+                    let target = href.trim_start_matches("mailto:");
+                    if !shown.ends_with(target) {
+                        self.links.push(href);
+                        let marker = format!("[{}]", self.links.len());
+                        self.text_raw(&marker);
+                    }
+                }
+            }
+            ("img", _) => {
+                if let Some(alt) = attr(attrs, "alt").filter(|a| !a.trim().is_empty()) {
+                    self.text(&format!("[image: {}]", alt.trim()));
+                }
+            }
+            ("table", false) => {
+                // A table inside a cell means the enclosing tables are only
+                // there for layout: flush them and let their content flow
+                if self.table_cell().is_some() {
+                    for table in std::mem::take(&mut self.tables) {
+                        self.emit_table(table);
+                    }
+                }
+                self.tables.push(Table::default());
+            }
+            ("table", true) => {
+                if let Some(table) = self.tables.pop() {
+                    self.emit_table(table);
+                }
+            }
+            ("tr", false) => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.close_cell();
+                    table.rows.push(Vec::new());
+                }
+            }
+            ("td" | "th", false) => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.close_cell();
+                    if table.rows.is_empty() {
+                        table.rows.push(Vec::new());
+                    }
+                    table.cell = Some(String::new());
+                }
+            }
+            ("td" | "th", true) => {
+                if let Some(table) = self.tables.last_mut() {
+                    table.close_cell();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn table_cell(&mut self) -> Option<&mut String> {
+        self.tables.last_mut().and_then(|t| t.cell.as_mut())
+    }
+
+    fn current_text(&self) -> &str {
+        match self.tables.last().and_then(|t| t.cell.as_ref()) {
+            Some(cell) => cell,
+            None => self.lines.last().map(|l| l.text.as_str()).unwrap_or(""),
+        }
+    }
+
+    /// Append text, collapsing whitespace outside `<pre>`
+    fn text(&mut self, s: &str) {
+        if self.pre > 0 {
+            let mut first = true;
+            for line in s.split('\n') {
+                if !first {
+                    self.newline();
+                }
+                self.text_raw(line);
+                first = false;
+            }
+            return;
+        }
+        for (i, word) in s.split_whitespace().enumerate() {
+            let leading = i > 0 || s.starts_with(char::is_whitespace);
+            if leading || self.space {
+                self.space = false;
+                let current = self.current_text();
+                if !current.is_empty() && !current.ends_with('\n') {
+                    self.text_raw(" ");
+                }
+            }
+            self.text_raw(word);
+        }
+        if s.ends_with(char::is_whitespace) {
+            self.space = true;
+        }
+    }
+
+    fn text_raw(&mut self, s: &str) {
+        if let Some(cell) = self.table_cell() {
+            cell.push_str(s);
+            return;
+        }
+        let quote = self.quote;
+        if self.lines.is_empty() {
+            self.lines.push(Line::default());
+        }
+        let line = self.lines.last_mut().expect("at least one line");
+        if line.text.is_empty() {
+            line.quote = quote;
+        }
+        line.text.push_str(s);
+    }
+
+    fn newline(&mut self) {
+        self.space = false;
+        if self.lines.last().is_some_and(|l| l.text.is_empty()) {
+            return;
+        }
+        self.lines.push(Line {
+            quote: self.quote,
+            text: String::new(),
+        });
+    }
+
+    /// End the paragraph, leaving an empty line before what follows
+    fn blank_line(&mut self) {
+        self.newline();
+        self.lines.push(Line {
+            quote: self.quote,
+            text: String::new(),
+        });
+    }
+
+    fn emit_table(&mut self, mut table: Table) {
+        table.close_cell();
+        let rows: Vec<Vec<String>> = table
+            .rows
+            .into_iter()
+            .filter(|r| r.iter().any(|c| !c.is_empty()))
+            .collect();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let layout = columns <= 1
+            || rows
+                .iter()
+                .flatten()
+                .any(|c| c.lines().any(|l| l.width() > MAX_CELL_WIDTH));
+
+        self.newline();
+        if layout {
+            for cell in rows.iter().flatten().filter(|c| !c.is_empty()) {
+                for line in cell.lines() {
+                    self.text_raw(line.trim());
+                    self.newline();
+                }
+                self.blank_line();
+            }
+            return;
+        }
+
+        let rows: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|r| {
+                r.iter()
+                    .map(|c| c.split_whitespace().collect::<Vec<_>>().join(" "))
+                    .collect()
+            })
+            .collect();
+        let mut widths = vec![0; columns];
+        for row in &rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.width());
+            }
+        }
+        for row in rows {
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                if i + 1 < row.len() {
+                    line.push_str(cell);
+                    line.push_str(&" ".repeat(widths[i] - cell.width() + 2));
+                } else {
+                    line.push_str(cell);
+                }
+            }
+            self.text_raw(line.trim_end());
+            self.newline();
+        }
+    }
+
+    fn finish(self, color: bool) -> String {
+        let mut out = String::new();
+        let mut blank = 0;
+        for line in &self.lines {
+            let text = line.text.trim_end();
+            if text.is_empty() {
+                blank += 1;
+                continue;
+            }
+            if !out.is_empty() {
+                out.push_str(if blank > 0 { "\n\n" } else { "\n" });
+            }
+            blank = 0;
+            if line.quote > 0 {
+                let prefix = "> ".repeat(line.quote);
+                if color {
+                    out.push_str(&format!("{}{}{}{}", DIM, prefix, text, RESET));
+                } else {
+                    out.push_str(&prefix);
+                    out.push_str(text);
+                }
+            } else {
+                out.push_str(text);
+            }
+        }
+        if !self.links.is_empty() {
+            out.push_str("\n\n");
+            for (i, link) in self.links.iter().enumerate() {
+                out.push_str(&format!("[{}] {}\n", i + 1, link));
+            }
+            out.truncate(out.trim_end().len());
+        }
+        out
+    }
+}
+
+impl Table {
+    fn close_cell(&mut self) {
+        if let Some(cell) = self.cell.take() {
+            if self.rows.is_empty() {
+                self.rows.push(Vec::new());
+            }
+            if let Some(row) = self.rows.last_mut() {
+                row.push(cell.trim().to_string());
+            }
+        }
+    }
+}
+
+/// Index just past the `>` closing the tag at the start of `s`,
+/// skipping `>` inside quoted attribute values
+/// Whether `s` starts with a tag: `<` followed by a name, `/name` or `!`
+fn starts_tag(s: &str) -> bool {
+    let mut chars = s.chars();
+    if chars.next() != Some('<') {
+        return false;
+    }
+    match chars.next() {
+        Some('/') => chars.next().is_some_and(|c| c.is_ascii_alphabetic()),
+        Some(c) => c.is_ascii_alphabetic() || c == '!',
+        None => false,
+    }
+}
+
+fn tag_end(s: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in s.char_indices().skip(1) {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('>', None) => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Value of attribute `name` in a tag's attribute text
+fn attr(attrs: &str, name: &str) -> Option<String> {
+    let lower = attrs.to_ascii_lowercase();
+    let mut from = 0;
+    while let Some(pos) = lower[from..].find(name) {
+        let start = from + pos;
+        from = start + name.len();
+        let boundary = start == 0 || lower.as_bytes()[start - 1].is_ascii_whitespace();
+        let rest = attrs[from..].trim_start();
+        if !boundary || !rest.starts_with('=') {
+            continue;
+        }
+        let value = rest[1..].trim_start();
+        let v = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => value[1..].split(q).next().unwrap_or(""),
+            _ => value.split_whitespace().next().unwrap_or(""),
+        };
+        return Some(decode_entities(v));
+    }
+    None
+}
+
+/// Decode named entities common in mail and numeric character references
+pub fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find('&') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let semicolon = rest.char_indices().take(12).find(|&(_, c)| c == ';');
+        let Some((end, _)) = semicolon else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            "ndash" => Some('–'),
+            "mdash" => Some('—'),
+            "hellip" => Some('…'),
+            "copy" => Some('©'),
+            "reg" => Some('®'),
+            "rsquo" => Some('’'),
+            "lsquo" => Some('‘'),
+            "rdquo" => Some('”'),
+            "ldquo" => Some('“'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_paragraphs_and_entities() {
+        let text = html_to_text("<p>Hello&nbsp;<b>world</b> &amp; co</p><p>Bye</p>", false);
+        assert!(text.contains("Hello world & co"), "{:?}", text);
+        assert!(text.contains("Bye"), "{:?}", text);
+    }
+
+    #[test]
+    fn unterminated_tag_is_kept_as_text() {
+        for html in ["<", "a <", "<p>a < b", "x <b", "<p>日本<語"] {
+            let text = html_to_text(html, false);
+            assert!(text.contains('<'), "{:?} -> {:?}", html, text);
+        }
+        assert!(html_to_text("<p>a < b", false).contains("a < b"));
+    }
+
+    #[test]
+    fn bare_lt_is_literal_text() {
+        assert_eq!(
+            html_to_text("<p>if a < b and c > d then</p>", false),
+            "if a < b and c > d then"
+        );
+        assert_eq!(html_to_text("x <3 y</p>", false), "x <3 y");
+    }
+
+    #[test]
+    fn renders_tables_as_columns() {
+        let html = "<table><tr><th>Name</th><th>Qty</th></tr>\
+                    <tr><td>Apple</td><td>3</td></tr>\
+                    <tr><td>Banana split</td><td>12</td></tr></table>";
+        assert_eq!(
+            html_to_text(html, false),
+            "Name          Qty\nApple         3\nBanana split  12"
+        );
+    }
+
+    #[test]
+    fn links_become_footnotes() {
+        let html = "<p>See <a href=\"https://example.com/a\">the docs</a> and \
+                    <a href=\"https://example.com/b\">this</a>.</p>";
+        assert_eq!(
+            html_to_text(html, false),
+            "See the docs[1] and this[2].\n\n[1] https://example.com/a\n[2] https://example.com/b"
+        );
+    }
+
+    #[test]
+    fn blockquotes_are_prefixed() {
+        let html = "<p>Reply</p><blockquote><p>Original line</p>\
+                    <blockquote>Nested</blockquote></blockquote><p>After</p>";
+        let text = html_to_text(html, false);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.first(), Some(&"Reply"));
+        assert!(lines.contains(&"> Original line"), "{:?}", text);
+        assert!(lines.contains(&"> > Nested"), "{:?}", text);
+        assert_eq!(lines.last(), Some(&"After"));
+    }
+
+    #[test]
+    fn quoted_gt_does_not_end_tag() {
+        let text = html_to_text("<a title=\"1 > 0\" href=\"x\">link</a> after", false);
+        assert!(text.contains("link"), "{:?}", text);
+        assert!(!text.contains("0\""), "{:?}", text);
+    }
+}
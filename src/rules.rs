@@ -0,0 +1,409 @@
+//! Client-side mail rules
+//!
+//! Rules are loaded from a TOML file and evaluated in order against inbox
+//! messages. Example:
+//!
+//! ```toml
+//! [[rule]]
+//! name = "Newsletters"
+//! from = "newsletter@"
+//! subject = "(?i)weekly digest"
+//! older_than = "7d"
+//! mark_read = true
+//! move_to = "Archive"
+//!
+//! [[rule]]
+//! name = "Invoices"
+//! subject = "(?i)invoice"
+//! has_attachments = true
+//! categorize = ["Finance"]
+//! flag = true
+//! forward = ["accounts@example.com"]
+//! stop = true
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, Utc};
+use clap::Subcommand;
+use fafafa_outlook_core::{Message, OutlookClient};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::dates;
+
+#[derive(Subcommand)]
+pub enum RulesCommand {
+    /// Apply rules to inbox messages
+    Apply {
+        /// Rules file (TOML)
+        #[arg(short, long, default_value = "rules.toml")]
+        file: String,
+        /// Number of inbox messages to evaluate
+        #[arg(short, long, default_value = "50")]
+        limit: u32,
+        /// Show what would change without modifying anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+struct RuleFile {
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDef {
+    name: String,
+    // Conditions (all must match)
+    from: Option<String>,
+    subject: Option<String>,
+    has_attachments: Option<bool>,
+    older_than: Option<String>,
+    // Actions
+    move_to: Option<String>,
+    #[serde(default)]
+    categorize: Vec<String>,
+    #[serde(default)]
+    flag: bool,
+    #[serde(default)]
+    mark_read: bool,
+    #[serde(default)]
+    forward: Vec<String>,
+    #[serde(default)]
+    delete: bool,
+    /// Stop evaluating further rules for a matched message
+    #[serde(default)]
+    stop: bool,
+}
+
+/// A rule with its patterns compiled
+struct Rule {
+    def: RuleDef,
+    subject: Option<Regex>,
+    older_than: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Action {
+    MarkRead,
+    Flag,
+    Categorize(Vec<String>),
+    Forward(Vec<String>),
+    Move(String),
+    Delete,
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::MarkRead => write!(f, "mark read"),
+            Action::Flag => write!(f, "flag"),
+            Action::Categorize(c) => write!(f, "categorize as {}", c.join(", ")),
+            Action::Forward(to) => write!(f, "forward to {}", to.join(", ")),
+            Action::Move(folder) => write!(f, "move to {}", folder),
+            Action::Delete => write!(f, "delete"),
+        }
+    }
+}
+
+impl Rule {
+    fn compile(def: RuleDef) -> anyhow::Result<Self> {
+        let subject = def
+            .subject
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("rule '{}': invalid subject regex: {}", def.name, e))?;
+        let older_than = def
+            .older_than
+            .as_deref()
+            .map(dates::parse_duration)
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("rule '{}': older_than: {}", def.name, e))?;
+        if def.move_to.is_some() && def.delete {
+            anyhow::bail!("rule '{}': move_to and delete are exclusive", def.name);
+        }
+        Ok(Self {
+            def,
+            subject,
+            older_than,
+        })
+    }
+
+    fn matches(&self, msg: &Message, now: DateTime<Utc>) -> bool {
+        if let Some(from) = &self.def.from {
+            let sender = msg
+                .from
+                .as_ref()
+                .map(|r| r.email_address.address.to_lowercase())
+                .unwrap_or_default();
+            if !sender.contains(&from.to_lowercase()) {
+                return false;
+            }
+        }
+        if let Some(re) = &self.subject {
+            if !re.is_match(msg.subject.as_deref().unwrap_or("")) {
+                return false;
+            }
+        }
+        if let Some(expected) = self.def.has_attachments {
+            if msg.has_attachments.unwrap_or(false) != expected {
+                return false;
+            }
+        }
+        if let Some(age) = self.older_than {
+            match msg.received_date_time {
+                Some(received) if now - received >= age => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Actions in execution order; move/delete come last since they
+    /// invalidate the message ID
+    fn actions(&self) -> Vec<Action> {
+        let def = &self.def;
+        let mut actions = Vec::new();
+        if def.mark_read {
+            actions.push(Action::MarkRead);
+        }
+        if def.flag {
+            actions.push(Action::Flag);
+        }
+        if !def.categorize.is_empty() {
+            actions.push(Action::Categorize(def.categorize.clone()));
+        }
+        if !def.forward.is_empty() {
+            actions.push(Action::Forward(def.forward.clone()));
+        }
+        if let Some(folder) = &def.move_to {
+            actions.push(Action::Move(folder.clone()));
+        }
+        if def.delete {
+            actions.push(Action::Delete);
+        }
+        actions
+    }
+}
+
+fn load(path: &Path) -> anyhow::Result<Vec<Rule>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("cannot read {}: {}", path.display(), e))?;
+    let file: RuleFile = toml::from_str(&text)?;
+    file.rules.into_iter().map(Rule::compile).collect()
+}
+
+pub async fn run(client: &OutlookClient, command: RulesCommand) -> anyhow::Result<()> {
+    match command {
+        RulesCommand::Apply {
+            file,
+            limit,
+            dry_run,
+        } => apply(client, Path::new(&file), limit, dry_run).await,
+    }
+}
+
+async fn apply(
+    client: &OutlookClient,
+    path: &Path,
+    limit: u32,
+    dry_run: bool,
+) -> anyhow::Result<()> {
+    let rules = load(path)?;
+    if rules.is_empty() {
+        println!("No rules in {}", path.display());
+        return Ok(());
+    }
+
+    let folders = resolve_folders(client, &rules).await?;
+    let messages = client.inbox(limit).await?;
+    let now = Utc::now();
+    let mut changed = 0;
+
+    for msg in &messages {
+        let mut planned: Vec<(String, Action)> = Vec::new();
+        for rule in &rules {
+            if !rule.matches(msg, now) {
+                continue;
+            }
+            for action in rule.actions() {
+                plan(&mut planned, &rule.def.name, action);
+            }
+            if rule.def.stop {
+                break;
+            }
+        }
+        if planned.is_empty() {
+            continue;
+        }
+        changed += 1;
+
+        let subject = msg.subject.as_deref().unwrap_or("(no subject)");
+        println!("{}", subject);
+        println!("    ID: {}", msg.id);
+        let mut gone = false;
+        for (rule, action) in planned {
+            // Once moved or deleted the message ID is no longer valid
+            if gone {
+                println!(
+                    "  [{}] skip {} (message already moved/deleted)",
+                    rule, action
+                );
+                continue;
+            }
+            if dry_run {
+                println!("  [{}] would {}", rule, action);
+                gone = matches!(action, Action::Move(_) | Action::Delete);
+                continue;
+            }
+            match &action {
+                Action::MarkRead => client.mark_as_read(&msg.id).await?,
+                Action::Flag => client.flag_message(&msg.id).await?,
+                Action::Categorize(categories) => {
+                    // The PATCH replaces the list: keep the message's own
+                    let mut all: Vec<&str> = msg
+                        .categories
+                        .iter()
+                        .flatten()
+                        .map(|s| s.as_str())
+                        .collect();
+                    for category in categories {
+                        if !all.iter().any(|c| c.eq_ignore_ascii_case(category)) {
+                            all.push(category);
+                        }
+                    }
+                    client.set_categories(&msg.id, &all).await?
+                }
+                Action::Forward(to) => {
+                    let to: Vec<&str> = to.iter().map(|s| s.as_str()).collect();
+                    client.forward(&msg.id, &to, None).await?
+                }
+                Action::Move(folder) => {
+                    client.move_message(&msg.id, &folders[folder]).await?;
+                    gone = true;
+                }
+                Action::Delete => {
+                    client.delete_message(&msg.id).await?;
+                    gone = true;
+                }
+            }
+            println!("  [{}] {}", rule, action);
+        }
+    }
+
+    println!();
+    let verb = if dry_run { "would change" } else { "changed" };
+    println!("{} of {} messages {}", changed, messages.len(), verb);
+    Ok(())
+}
+
+/// Add an action to a message's plan. Categories from several rules are
+/// merged into one action, since each PATCH replaces the whole list.
+fn plan(planned: &mut Vec<(String, Action)>, rule: &str, action: Action) {
+    if let Action::Categorize(add) = &action {
+        let existing = planned
+            .iter_mut()
+            .find(|(_, a)| matches!(a, Action::Categorize(_)));
+        if let Some((rules, Action::Categorize(categories))) = existing {
+            let mut merged = false;
+            for category in add {
+                if !categories.iter().any(|c| c.eq_ignore_ascii_case(category)) {
+                    categories.push(category.clone());
+                    merged = true;
+                }
+            }
+            if merged {
+                rules.push_str(&format!(", {}", rule));
+            }
+            return;
+        }
+    }
+    if !planned.iter().any(|(_, a)| *a == action) {
+        planned.push((rule.to_string(), action));
+    }
+}
+
+/// Resolve every `move_to` folder (display name or ID) to a folder ID
+/// before touching any message
+async fn resolve_folders(
+    client: &OutlookClient,
+    rules: &[Rule],
+) -> anyhow::Result<HashMap<String, String>> {
+    let mut resolved = HashMap::new();
+    if rules.iter().all(|r| r.def.move_to.is_none()) {
+        return Ok(resolved);
+    }
+    let folders = client.list_folders().await?;
+    for rule in rules {
+        let Some(name) = &rule.def.move_to else {
+            continue;
+        };
+        let folder = folders
+            .iter()
+            .find(|f| f.display_name.eq_ignore_ascii_case(name))
+            .or_else(|| folders.iter().find(|f| f.id == *name))
+            .ok_or_else(|| {
+                anyhow::anyhow!("rule '{}': unknown folder '{}'", rule.def.name, name)
+            })?;
+        resolved.insert(name.clone(), folder.id.clone());
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(toml: &str) -> anyhow::Result<Vec<Rule>> {
+        let file: RuleFile = toml::from_str(toml)?;
+        file.rules.into_iter().map(Rule::compile).collect()
+    }
+
+    #[test]
+    fn categories_from_several_rules_are_merged() {
+        let mut planned = Vec::new();
+        plan(
+            &mut planned,
+            "a",
+            Action::Categorize(vec!["Finance".into()]),
+        );
+        plan(&mut planned, "a", Action::Flag);
+        plan(
+            &mut planned,
+            "b",
+            Action::Categorize(vec!["finance".into(), "Q3".into()]),
+        );
+        plan(&mut planned, "c", Action::Flag);
+        assert_eq!(
+            planned,
+            [
+                (
+                    "a, b".to_string(),
+                    Action::Categorize(vec!["Finance".into(), "Q3".into()])
+                ),
+                ("a".to_string(), Action::Flag),
+            ]
+        );
+    }
+
+    #[test]
+    fn older_than_accepts_durations() {
+        let rules = compile("[[rule]]\nname = \"a\"\nolder_than = \"1d12h\"\n").unwrap();
+        assert_eq!(rules[0].older_than, Some(Duration::hours(36)));
+    }
+
+    #[test]
+    fn older_than_rejects_invalid_input() {
+        for age in ["", "d", "7x", "7日", "日", "0d"] {
+            let toml = format!("[[rule]]\nname = \"a\"\nolder_than = \"{}\"\n", age);
+            let err = compile(&toml).err().expect(age).to_string();
+            assert!(err.starts_with("rule 'a': older_than:"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Scheduled (deferred) sending
+//!
+//! Messages sent with `--at` carry a deferred send time and wait in the
+//! Outbox until Exchange delivers them. Times are read and shown in the
+//! mailbox timezone, like the calendar commands.
+
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
+use fafafa_outlook_core::OutlookClient;
+
+use crate::dates::Clock;
+use crate::settings;
+
+#[derive(Subcommand)]
+pub enum ScheduledCommand {
+    /// List messages waiting for deferred delivery (default)
+    List {
+        /// Number of messages to show
+        #[arg(short, long, default_value = "20")]
+        limit: u32,
+    },
+    /// Cancel a scheduled message and move it back to Drafts
+    Cancel {
+        /// Message ID
+        id: String,
+    },
+}
+
+/// A validated deferred send time
+pub struct SendTime {
+    pub at: DateTime<Utc>,
+    /// `at` as mailbox wall-clock time, for messages
+    pub display: String,
+}
+
+/// Parse a send time (ISO 8601 or relative, e.g. 'tomorrow 9am') in the
+/// mailbox timezone
+pub async fn parse_send_time(client: &OutlookClient, s: &str) -> anyhow::Result<SendTime> {
+    let timezone = settings::mailbox_timezone(client).await?;
+    let clock = Clock::new(&timezone)?;
+    let wall = clock.parse(s)?;
+    let at = clock.to_utc(wall);
+    let display = format!("{} ({})", wall.format("%Y-%m-%d %H:%M"), timezone);
+    if at <= Utc::now() {
+        anyhow::bail!("send time {} is in the past", display);
+    }
+    Ok(SendTime { at, display })
+}
+
+pub async fn run(client: &OutlookClient, command: ScheduledCommand) -> anyhow::Result<()> {
+    match command {
+        ScheduledCommand::List { limit } => {
+            let messages = client.list_scheduled(limit).await?;
+            if messages.is_empty() {
+                println!("No scheduled messages");
+            } else {
+                let timezone = settings::mailbox_timezone(client).await?;
+                let clock = Clock::new(&timezone)?;
+                println!("Scheduled messages ({}):", timezone);
+                for msg in messages {
+                    let at = msg
+                        .deferred_send_time
+                        .map(|t| clock.wall_clock(t).format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_else(|| "?".to_string());
+                    let to = msg
+                        .to_recipients
+                        .as_deref()
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|r| r.email_address.address.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    let subject = msg.subject.as_deref().unwrap_or("(no subject)");
+                    println!("  {} - {} - {}", at, to, subject);
+                    println!("    ID: {}", msg.id);
+                }
+            }
+        }
+        ScheduledCommand::Cancel { id } => {
+            let draft = client.cancel_scheduled(&id).await?;
+            println!("Scheduled send cancelled; moved to drafts: {}", draft.id);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+//! Mailbox settings: timezone, language, date/time formats and working hours
+
+use clap::{Args, Subcommand};
+use fafafa_outlook_core::{MailboxSettingsUpdate, OutlookClient, WorkingHours};
+
+/// Timezone used when the mailbox has none configured
+pub const FALLBACK_TIMEZONE: &str = "UTC";
+
+const WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+#[derive(Subcommand)]
+pub enum SettingsCommand {
+    /// Show mailbox settings
+    Show,
+    /// Update mailbox settings
+    Set(SetArgs),
+}
+
+#[derive(Args)]
+pub struct SetArgs {
+    /// Mailbox timezone (e.g. "China Standard Time" or "Asia/Shanghai")
+    #[arg(long)]
+    timezone: Option<String>,
+    /// Language locale (e.g. zh-CN, en-US)
+    #[arg(long)]
+    language: Option<String>,
+    /// Date format (e.g. yyyy-MM-dd)
+    #[arg(long)]
+    date_format: Option<String>,
+    /// Time format (e.g. HH:mm)
+    #[arg(long)]
+    time_format: Option<String>,
+    /// Working hours start (HH:MM)
+    #[arg(long)]
+    work_start: Option<String>,
+    /// Working hours end (HH:MM)
+    #[arg(long)]
+    work_end: Option<String>,
+    /// Working days (comma-separated, e.g. mon,tue,wed,thu,fri)
+    #[arg(long)]
+    work_days: Option<String>,
+}
+
+/// The mailbox timezone, or [`FALLBACK_TIMEZONE`] if unset
+pub async fn mailbox_timezone(client: &OutlookClient) -> anyhow::Result<String> {
+    let settings = client.get_mailbox_settings().await?;
+    Ok(settings
+        .time_zone
+        .filter(|tz| !tz.is_empty())
+        .unwrap_or_else(|| FALLBACK_TIMEZONE.to_string()))
+}
+
+pub async fn run(client: &OutlookClient, command: SettingsCommand) -> anyhow::Result<()> {
+    match command {
+        SettingsCommand::Show => {
+            let settings = client.get_mailbox_settings().await?;
+            println!(
+                "Timezone: {}",
+                settings.time_zone.as_deref().unwrap_or("(not set)")
+            );
+            if let Some(lang) = &settings.language {
+                println!(
+                    "Language: {}{}",
+                    lang.locale.as_deref().unwrap_or("?"),
+                    lang.display_name
+                        .as_deref()
+                        .map(|n| format!(" ({})", n))
+                        .unwrap_or_default()
+                );
+            }
+            if let Some(fmt) = &settings.date_format {
+                println!("Date format: {}", fmt);
+            }
+            if let Some(fmt) = &settings.time_format {
+                println!("Time format: {}", fmt);
+            }
+            if let Some(hours) = &settings.working_hours {
+                println!(
+                    "Working hours: {} - {} ({})",
+                    short_time(&hours.start_time),
+                    short_time(&hours.end_time),
+                    hours
+                        .time_zone
+                        .as_ref()
+                        .map(|tz| tz.name.as_str())
+                        .unwrap_or("?")
+                );
+                println!("Working days: {}", hours.days_of_week.join(", "));
+            }
+        }
+        SettingsCommand::Set(args) => {
+            let mut update = MailboxSettingsUpdate::new();
+            if let Some(tz) = args.timezone {
+                update = update.time_zone(tz);
+            }
+            if let Some(lang) = args.language {
+                update = update.language(lang);
+            }
+            if let Some(fmt) = args.date_format {
+                update = update.date_format(fmt);
+            }
+            if let Some(fmt) = args.time_format {
+                update = update.time_format(fmt);
+            }
+            if args.work_start.is_some() || args.work_end.is_some() || args.work_days.is_some() {
+                // Graph replaces working hours as a whole, so merge with the current value
+                let current = client.get_mailbox_settings().await?.working_hours;
+                let mut hours = current.unwrap_or_else(WorkingHours::default);
+                if let Some(start) = args.work_start {
+                    hours.start_time = graph_time(&start)?;
+                }
+                if let Some(end) = args.work_end {
+                    hours.end_time = graph_time(&end)?;
+                }
+                if let Some(days) = args.work_days {
+                    hours.days_of_week = parse_days(&days)?;
+                }
+                update = update.working_hours(hours);
+            }
+            if update.is_empty() {
+                anyhow::bail!("nothing to update (see `settings set --help`)");
+            }
+            client.update_mailbox_settings(update).await?;
+            println!("Mailbox settings updated");
+        }
+    }
+    Ok(())
+}
+
+/// `HH:MM` -> Graph's `HH:MM:SS.0000000`
+fn graph_time(s: &str) -> anyhow::Result<String> {
+    let t = chrono::NaiveTime::parse_from_str(s, "%H:%M")
+        .map_err(|_| anyhow::anyhow!("invalid time '{}' (expected HH:MM)", s))?;
+    // chrono has no 7-digit fraction specifier
+    Ok(format!("{}.0000000", t.format("%H:%M:%S")))
+}
+
+/// `08:00:00.0000000` -> `08:00`
+fn short_time(s: &str) -> &str {
+    s.get(..5).unwrap_or(s)
+}
+
+/// Expand `mon,tue` style day lists to Graph day names
+pub fn parse_days(s: &str) -> anyhow::Result<Vec<String>> {
+    s.split(',')
+        .map(|d| d.trim().to_lowercase())
+        .filter(|d| !d.is_empty())
+        .map(|d| {
+            WEEKDAYS
+                .iter()
+                .find(|full| d.len() >= 2 && full.starts_with(&d))
+                .map(|full| full.to_string())
+                .ok_or_else(|| anyhow::anyhow!("invalid day '{}'", d))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graph_time_table() {
+        assert_eq!(graph_time("08:00").unwrap(), "08:00:00.0000000");
+        assert_eq!(graph_time("17:30").unwrap(), "17:30:00.0000000");
+        for input in ["", "8", "24:00", "08:60", "8am"] {
+            assert!(graph_time(input).is_err(), "{}", input);
+        }
+        assert_eq!(short_time(&graph_time("09:15").unwrap()), "09:15");
+    }
+}
@@ -0,0 +1,330 @@
+//! Graph change-notification subscriptions
+//!
+//! `create/list/renew/delete` manage subscriptions through the core client.
+//! `serve` runs a minimal local HTTP listener that answers Graph's
+//! validation handshake and prints incoming notifications.
+
+use chrono::{Duration, SecondsFormat, Utc};
+use clap::{Subcommand, ValueEnum};
+use fafafa_outlook_core::{NewSubscription, OutlookClient};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Default subscription lifetime in minutes (Outlook resources allow up to 10080)
+const DEFAULT_MINUTES: i64 = 4320;
+
+/// Time allowed to receive a request; Graph expects an answer to the
+/// validation handshake within 10 seconds
+const READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Subcommand)]
+pub enum SubscriptionCommand {
+    /// Create a change-notification subscription
+    Create {
+        /// Resource to watch
+        #[arg(value_enum)]
+        resource: Resource,
+        /// Public HTTPS URL that receives notifications
+        #[arg(short, long)]
+        url: String,
+        /// Change types (comma-separated: created,updated,deleted)
+        #[arg(long, default_value = "created")]
+        change_type: String,
+        /// Lifetime in minutes
+        #[arg(short, long, default_value_t = DEFAULT_MINUTES)]
+        minutes: i64,
+        /// Opaque value echoed back in every notification
+        #[arg(long)]
+        client_state: Option<String>,
+    },
+    /// List active subscriptions
+    List,
+    /// Extend a subscription's expiration
+    Renew {
+        /// Subscription ID
+        id: String,
+        /// New lifetime in minutes from now
+        #[arg(short, long, default_value_t = DEFAULT_MINUTES)]
+        minutes: i64,
+    },
+    /// Delete a subscription
+    Delete {
+        /// Subscription ID
+        id: String,
+    },
+    /// Run a local listener for notifications
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        bind: String,
+        /// Reject notifications whose clientState does not match
+        #[arg(long)]
+        client_state: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Resource {
+    Mail,
+    Events,
+    Contacts,
+}
+
+impl Resource {
+    fn path(self) -> &'static str {
+        match self {
+            Resource::Mail => "me/messages",
+            Resource::Events => "me/events",
+            Resource::Contacts => "me/contacts",
+        }
+    }
+}
+
+fn expiration(minutes: i64) -> String {
+    (Utc::now() + Duration::minutes(minutes)).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+pub async fn run(client: &OutlookClient, command: SubscriptionCommand) -> anyhow::Result<()> {
+    match command {
+        SubscriptionCommand::Create {
+            resource,
+            url,
+            change_type,
+            minutes,
+            client_state,
+        } => {
+            let mut subscription =
+                NewSubscription::new(resource.path(), &change_type, &url, expiration(minutes));
+            if let Some(state) = client_state {
+                subscription = subscription.client_state(state);
+            }
+            let created = client.create_subscription(subscription).await?;
+            println!("Subscription created: {}", created.id);
+            if let Some(expires) = created.expiration_date_time {
+                println!("Expires: {}", expires);
+            }
+        }
+        SubscriptionCommand::List => {
+            let subscriptions = client.list_subscriptions().await?;
+            if subscriptions.is_empty() {
+                println!("No subscriptions");
+            } else {
+                println!("Subscriptions:");
+                for sub in subscriptions {
+                    println!(
+                        "  {} [{}] -> {}",
+                        sub.resource.as_deref().unwrap_or("?"),
+                        sub.change_type.as_deref().unwrap_or("?"),
+                        sub.notification_url.as_deref().unwrap_or("?")
+                    );
+                    println!("    ID: {}", sub.id);
+                    if let Some(expires) = sub.expiration_date_time {
+                        println!("    Expires: {}", expires);
+                    }
+                }
+            }
+        }
+        SubscriptionCommand::Renew { id, minutes } => {
+            let renewed = client.renew_subscription(&id, &expiration(minutes)).await?;
+            println!(
+                "Subscription renewed: {} (expires {})",
+                id,
+                renewed.expiration_date_time.unwrap_or_default()
+            );
+        }
+        SubscriptionCommand::Delete { id } => {
+            client.delete_subscription(&id).await?;
+            println!("Subscription deleted: {}", id);
+        }
+        SubscriptionCommand::Serve { bind, client_state } => {
+            serve(&bind, client_state.as_deref()).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Accept connections until Ctrl-C, handling each on its own task
+async fn serve(bind: &str, client_state: Option<&str>) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(bind).await?;
+    eprintln!("Listening for notifications on http://{}", bind);
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            _ = tokio::signal::ctrl_c() => break,
+        };
+        let stream = match accepted {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                eprintln!("Accept failed: {}", e);
+                continue;
+            }
+        };
+        let client_state = client_state.map(str::to_string);
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, client_state.as_deref()).await {
+                eprintln!("Request failed: {}", e);
+            }
+        });
+    }
+    Ok(())
+}
+
+async fn handle(mut stream: TcpStream, client_state: Option<&str>) -> anyhow::Result<()> {
+    let (target, body) = tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream))
+        .await
+        .map_err(|_| anyhow::anyhow!("timed out reading the request"))??;
+
+    // Validation handshake: echo the token back as plain text
+    if let Some(token) = query_param(&target, "validationToken") {
+        eprintln!("Validation handshake answered");
+        return respond(&mut stream, "200 OK", "text/plain", &token).await;
+    }
+
+    let payload: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(v) => v,
+        Err(_) => return respond(&mut stream, "400 Bad Request", "text/plain", "").await,
+    };
+    // Acknowledge first; Graph retries slow endpoints
+    respond(&mut stream, "202 Accepted", "text/plain", "").await?;
+
+    let notifications = payload
+        .get("value")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for n in notifications {
+        let state = n.get("clientState").and_then(|v| v.as_str());
+        if client_state.is_some() && state != client_state {
+            eprintln!("Ignoring notification with mismatched clientState");
+            continue;
+        }
+        println!("{}", serde_json::to_string(&n)?);
+    }
+    Ok(())
+}
+
+/// Read a request line, headers and a `Content-Length` body
+async fn read_request(stream: &mut TcpStream) -> anyhow::Result<(String, Vec<u8>)> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let header_end = loop {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            anyhow::bail!("connection closed before headers were complete");
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut lines = head.lines();
+    let target = lines
+        .next()
+        .and_then(|l| l.split_whitespace().nth(1))
+        .unwrap_or("/")
+        .to_string();
+    let content_length = lines
+        .filter_map(|l| l.split_once(':'))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+
+    let mut body = buf[header_end..].to_vec();
+    while body.len() < content_length {
+        let n = stream.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..n]);
+    }
+    Ok((target, body))
+}
+
+async fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> anyhow::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn query_param(target: &str, name: &str) -> Option<String> {
+    let query = target.split_once('?')?.1;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == name)
+        .map(|(_, v)| percent_decode(v))
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decode_table() {
+        let cases = [
+            ("plain", "plain"),
+            ("a+b", "a b"),
+            ("Validation%3A%20Testing", "Validation: Testing"),
+            ("%e4%bd%a0%E5%A5%BD", "你好"),
+            ("100%", "100%"),
+            ("50%2", "50%2"),
+            ("%zz", "%zz"),
+            ("%41", "A"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(percent_decode(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn query_param_table() {
+        let target = "/notify?foo=1&validationToken=Validation%3A+Token&bar";
+        assert_eq!(
+            query_param(target, "validationToken").as_deref(),
+            Some("Validation: Token")
+        );
+        assert_eq!(query_param(target, "foo").as_deref(), Some("1"));
+        assert_eq!(query_param(target, "bar"), None);
+        assert_eq!(query_param(target, "validation"), None);
+        assert_eq!(query_param("/notify", "validationToken"), None);
+        assert_eq!(query_param("/?a=", "a").as_deref(), Some(""));
+    }
+}
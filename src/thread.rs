@@ -0,0 +1,156 @@
+//! Conversation (thread) views
+
+use fafafa_outlook_core::{Message, OutlookClient};
+
+/// Markers that start a quoted previous message in replies
+const QUOTE_MARKERS: [&str; 5] = [
+    "-----Original Message-----",
+    "________________________________",
+    "-----原始邮件-----",
+    "发件人：",
+    "发件人:",
+];
+
+/// Print every message in the conversation of `id`, oldest first
+pub async fn show(client: &OutlookClient, id: &str) -> anyhow::Result<()> {
+    let msg = client.get_message_with_body(id).await?;
+    let Some(conversation_id) = msg.conversation_id else {
+        anyhow::bail!("message {} has no conversation ID", id);
+    };
+
+    let mut messages = client.list_conversation_messages(&conversation_id).await?;
+    messages.sort_by_key(|m| m.received_date_time);
+
+    let subject = messages
+        .first()
+        .and_then(|m| m.subject.as_deref())
+        .unwrap_or("(no subject)");
+    println!("Thread: {} ({} messages)", subject, messages.len());
+
+    for msg in &messages {
+        println!();
+        println!("{}", "─".repeat(60));
+        let from = msg
+            .from
+            .as_ref()
+            .map(|r| r.email_address.address.as_str())
+            .unwrap_or("unknown");
+        let date = msg
+            .received_date_time
+            .map(|d| d.to_string())
+            .unwrap_or_default();
+        println!("From: {}", from);
+        println!("Date: {}", date);
+        println!("ID: {}", msg.id);
+        println!();
+        if let Some(body) = &msg.body {
+            let text = if body.content_type.eq_ignore_ascii_case("html") {
+                crate::render::html_to_text(&body.content, false)
+            } else {
+                body.content.clone()
+            };
+            let (visible, quoted) = collapse_quoted(&text);
+            println!("{}", visible);
+            if quoted > 0 {
+                println!("[{} quoted lines hidden]", quoted);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Print messages grouped by conversation, newest thread first
+pub fn print_threads(messages: &[Message]) {
+    if messages.is_empty() {
+        println!("(no messages)");
+        return;
+    }
+
+    // Preserve the incoming (newest first) order of each thread's first appearance
+    let mut threads: Vec<(&str, Vec<&Message>)> = Vec::new();
+    for msg in messages {
+        let key = msg.conversation_id.as_deref().unwrap_or(msg.id.as_str());
+        match threads.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(msg),
+            None => threads.push((key, vec![msg])),
+        }
+    }
+
+    for (i, (_, group)) in threads.iter().enumerate() {
+        let latest = group[0];
+        let subject = latest.subject.as_deref().unwrap_or("(no subject)");
+        let unread = group.iter().any(|m| !m.is_read.unwrap_or(false));
+        let read_mark = if unread { "*" } else { " " };
+        let count = if group.len() > 1 {
+            format!(" ({})", group.len())
+        } else {
+            String::new()
+        };
+        let mut senders: Vec<&str> = Vec::new();
+        for m in group.iter().rev() {
+            let from = m
+                .from
+                .as_ref()
+                .map(|r| r.email_address.address.as_str())
+                .unwrap_or("unknown");
+            if !senders.contains(&from) {
+                senders.push(from);
+            }
+        }
+
+        println!(
+            "{:2}.{} {} - {}{}",
+            i + 1,
+            read_mark,
+            senders.join(", "),
+            subject,
+            count
+        );
+        println!("      ID: {}", latest.id);
+    }
+    println!();
+    println!("* = unread");
+}
+
+/// Split off quoted history; returns the visible text and hidden line count
+pub fn collapse_quoted(text: &str) -> (String, usize) {
+    let lines: Vec<&str> = text.lines().collect();
+    let cut = lines
+        .iter()
+        .position(|line| {
+            let t = line.trim();
+            QUOTE_MARKERS.iter().any(|m| t.starts_with(m))
+                || (t.starts_with("On ") && t.ends_with("wrote:"))
+        })
+        .unwrap_or(lines.len());
+
+    let mut visible = Vec::new();
+    let mut hidden = lines.len() - cut;
+    for line in &lines[..cut] {
+        if line.trim_start().starts_with('>') {
+            hidden += 1;
+        } else {
+            visible.push(*line);
+        }
+    }
+    (visible.join("\n").trim_end().to_string(), hidden)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collapses_quote_headers() {
+        for header in [
+            "发件人： 张三",
+            "发件人: 张三",
+            "-----Original Message-----",
+        ] {
+            let text = format!("好的\n\n{}\n发送时间： 2024年5月1日\n原文", header);
+            let (visible, hidden) = collapse_quoted(&text);
+            assert_eq!(visible.trim(), "好的", "{}", header);
+            assert_eq!(hidden, 3, "{}", header);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Watch the mailbox for new messages
+//!
+//! Polls `poll_new_messages` on an interval and either prints each new
+//! message or forwards it to a webhook.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use chrono::{SecondsFormat, Utc};
+use fafafa_outlook_core::OutlookClient;
+
+use crate::webhook::{Delivery, MailEvent, WebhookForwarder};
+
+pub struct WatchOptions {
+    pub since: Option<String>,
+    pub interval: u64,
+    pub limit: u32,
+    pub include_body: bool,
+}
+
+pub async fn run(
+    client: &OutlookClient,
+    options: WatchOptions,
+    forwarder: Option<WebhookForwarder>,
+) -> anyhow::Result<()> {
+    let mut since = options
+        .since
+        .unwrap_or_else(|| Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut ticker = tokio::time::interval(Duration::from_secs(options.interval.max(1)));
+
+    eprintln!("Watching for new messages since {} (Ctrl-C to stop)", since);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+
+        let messages = match client.poll_new_messages(&since, options.limit).await {
+            Ok(messages) => messages,
+            Err(e) => {
+                eprintln!("Poll failed: {}", e);
+                continue;
+            }
+        };
+
+        // Oldest first so webhook consumers see events in arrival order
+        let mut new: Vec<_> = messages
+            .into_iter()
+            .filter(|m| !seen.contains(&m.id))
+            .collect();
+        new.sort_by_key(|m| m.received_date_time);
+
+        for msg in new {
+            match &forwarder {
+                Some(forwarder) => {
+                    // Without the body the event still goes out, metadata only
+                    let full = if options.include_body {
+                        match client.get_message_with_body(&msg.id).await {
+                            Ok(full) => Some(full),
+                            Err(e) => {
+                                eprintln!(
+                                    "Failed to fetch body of {}: {}; forwarding without it",
+                                    msg.id, e
+                                );
+                                None
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    let event = match &full {
+                        Some(full) => MailEvent::new(full, true),
+                        None => MailEvent::new(&msg, false),
+                    };
+                    match forwarder.forward(&event).await {
+                        Ok(Delivery::Delivered) => println!("Forwarded: {}", msg.id),
+                        Ok(Delivery::DeadLettered) => println!("Dead-lettered: {}", msg.id),
+                        Ok(Delivery::Dropped) => println!("Dropped: {}", msg.id),
+                        Err(e) => eprintln!("Failed to forward {}: {}", msg.id, e),
+                    }
+                }
+                None => {
+                    let from = msg
+                        .from
+                        .as_ref()
+                        .map(|r| r.email_address.address.as_str())
+                        .unwrap_or("unknown");
+                    let subject = msg.subject.as_deref().unwrap_or("(no subject)");
+                    println!("{} - {}", from, subject);
+                    println!("  ID: {}", msg.id);
+                }
+            }
+
+            // Only handled messages count as seen
+            if let Some(received) = msg.received_date_time {
+                let ts = received.to_rfc3339_opts(SecondsFormat::Secs, true);
+                if ts > since {
+                    since = ts;
+                }
+            }
+            seen.insert(msg.id.clone());
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,338 @@
+//! Webhook forwarding of new mail events
+//!
+//! Each new message is POSTed as a JSON payload to the configured URL.
+//! When a secret is set, the raw request body is signed with HMAC-SHA256
+//! and the hex digest is sent in the `X-Outlook-Signature` header as
+//! `sha256=<digest>`. Failed deliveries are retried with exponential
+//! backoff; payloads that still fail are appended to a dead-letter file
+//! (one JSON object per line) or, without one, dropped.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+use fafafa_outlook_core::Message;
+
+/// Header carrying the HMAC signature of the payload
+pub const SIGNATURE_HEADER: &str = "X-Outlook-Signature";
+
+/// Payload sent for every new message
+#[derive(Debug, Serialize)]
+pub struct MailEvent {
+    pub event: &'static str,
+    pub id: String,
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    pub received_date_time: Option<String>,
+    pub is_read: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+impl MailEvent {
+    pub fn new(msg: &Message, include_body: bool) -> Self {
+        Self {
+            event: "message.created",
+            id: msg.id.clone(),
+            subject: msg.subject.clone(),
+            from: msg.from.as_ref().map(|r| r.email_address.address.clone()),
+            received_date_time: msg.received_date_time.map(|d| d.to_rfc3339()),
+            is_read: msg.is_read.unwrap_or(false),
+            body: if include_body {
+                msg.body.as_ref().map(|b| b.content.clone())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Webhook delivery settings
+pub struct WebhookConfig {
+    pub url: String,
+    pub secret: Option<String>,
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled after each attempt
+    pub retry_delay: Duration,
+    pub dead_letter: Option<PathBuf>,
+}
+
+/// Default delay before the first retry
+pub const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Outcome of forwarding one event
+#[derive(Debug, PartialEq, Eq)]
+pub enum Delivery {
+    Delivered,
+    DeadLettered,
+    /// Delivery failed and the event could not be dead-lettered
+    Dropped,
+}
+
+/// Delivers mail events to a webhook endpoint
+pub struct WebhookForwarder {
+    config: WebhookConfig,
+    http: reqwest::Client,
+}
+
+impl WebhookForwarder {
+    pub fn new(config: WebhookConfig) -> Self {
+        Self {
+            config,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Deliver an event, retrying with backoff and dead-lettering on failure
+    pub async fn forward(&self, event: &MailEvent) -> anyhow::Result<Delivery> {
+        let body = serde_json::to_vec(event)?;
+        let signature = self.config.secret.as_deref().map(|s| sign(s, &body));
+
+        let mut delay = self.config.retry_delay;
+        let mut last_error = String::new();
+        for attempt in 0..=self.config.max_retries {
+            if attempt > 0 {
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(Duration::from_secs(60));
+            }
+            match self.send(&body, signature.as_deref()).await {
+                Ok(()) => return Ok(Delivery::Delivered),
+                Err(e) => {
+                    last_error = e.to_string();
+                    eprintln!(
+                        "Webhook delivery failed for {} (attempt {}/{}): {}",
+                        event.id,
+                        attempt + 1,
+                        self.config.max_retries + 1,
+                        last_error
+                    );
+                }
+            }
+        }
+
+        let Some(path) = &self.config.dead_letter else {
+            return Ok(Delivery::Dropped);
+        };
+        let entry = serde_json::json!({
+            "url": self.config.url,
+            "error": last_error,
+            "payload": event,
+        });
+        let written = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", entry));
+        match written {
+            Ok(()) => Ok(Delivery::DeadLettered),
+            Err(e) => {
+                eprintln!("Failed to write dead-letter file {}: {}", path.display(), e);
+                Ok(Delivery::Dropped)
+            }
+        }
+    }
+
+    async fn send(&self, body: &[u8], signature: Option<&str>) -> anyhow::Result<()> {
+        let mut request = self
+            .http
+            .post(&self.config.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_vec());
+        if let Some(sig) = signature {
+            request = request.header(SIGNATURE_HEADER, sig);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("HTTP {}", status);
+        }
+        Ok(())
+    }
+}
+
+/// Compute the `sha256=<hex>` signature of a payload
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// A request as seen by the test server
+    struct Received {
+        signature: Option<String>,
+        body: Vec<u8>,
+    }
+
+    /// Serve HTTP on a local port, answering with `statuses` in turn (the
+    /// last one repeats). Returns the URL and the requests received.
+    async fn serve(statuses: Vec<u16>) -> (String, Arc<Mutex<Vec<Received>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        tokio::spawn(async move {
+            for n in 0.. {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let request = read_request(&mut stream).await;
+                log.lock().unwrap().push(request);
+                let status = statuses[n.min(statuses.len() - 1)];
+                let response = format!(
+                    "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> Received {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 4096];
+        let header_end = loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..n]);
+            if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&data[..header_end]).to_string();
+        let header = |name: &str| {
+            head.lines().find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.eq_ignore_ascii_case(name)
+                    .then(|| value.trim().to_string())
+            })
+        };
+        let length: usize = header("content-length").unwrap().parse().unwrap();
+        while data.len() < header_end + length {
+            let n = stream.read(&mut buf).await.unwrap();
+            data.extend_from_slice(&buf[..n]);
+        }
+        Received {
+            signature: header(SIGNATURE_HEADER),
+            body: data[header_end..header_end + length].to_vec(),
+        }
+    }
+
+    fn event() -> MailEvent {
+        MailEvent {
+            event: "message.created",
+            id: "AAMk1".to_string(),
+            subject: Some("Hello".to_string()),
+            from: Some("alice@example.com".to_string()),
+            received_date_time: Some("2024-05-01T09:00:00+00:00".to_string()),
+            is_read: false,
+            body: None,
+        }
+    }
+
+    fn forwarder(url: String, max_retries: u32, dead_letter: Option<PathBuf>) -> WebhookForwarder {
+        WebhookForwarder::new(WebhookConfig {
+            url,
+            secret: Some("s3cret".to_string()),
+            max_retries,
+            retry_delay: Duration::from_millis(1),
+            dead_letter,
+        })
+    }
+
+    fn dead_letter_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "outlook-webhook-test-{}-{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn sign_matches_known_digest() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn delivers_signed_payload() {
+        let (url, received) = serve(vec![200]).await;
+        let outcome = forwarder(url, 0, None).forward(&event()).await.unwrap();
+        assert_eq!(outcome, Delivery::Delivered);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let payload: serde_json::Value = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(payload["event"], "message.created");
+        assert_eq!(payload["id"], "AAMk1");
+        assert_eq!(payload["from"], "alice@example.com");
+        assert!(payload.get("body").is_none());
+        assert_eq!(
+            received[0].signature.as_deref(),
+            Some(sign("s3cret", &received[0].body).as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_until_accepted() {
+        let (url, received) = serve(vec![500, 503, 200]).await;
+        let outcome = forwarder(url, 3, None).forward(&event()).await.unwrap();
+        assert_eq!(outcome, Delivery::Delivered);
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn dead_letters_after_retries() {
+        let (url, received) = serve(vec![500]).await;
+        let path = dead_letter_path("dead");
+        let outcome = forwarder(url.clone(), 2, Some(path.clone()))
+            .forward(&event())
+            .await
+            .unwrap();
+        assert_eq!(outcome, Delivery::DeadLettered);
+        assert_eq!(received.lock().unwrap().len(), 3);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry["url"], url);
+        assert_eq!(entry["error"], "HTTP 500 Internal Server Error");
+        assert_eq!(entry["payload"]["id"], "AAMk1");
+    }
+
+    #[tokio::test]
+    async fn drops_without_dead_letter() {
+        let (url, _) = serve(vec![500]).await;
+        let outcome = forwarder(url, 0, None).forward(&event()).await.unwrap();
+        assert_eq!(outcome, Delivery::Dropped);
+    }
+
+    #[tokio::test]
+    async fn drops_when_dead_letter_unwritable() {
+        let (url, _) = serve(vec![500]).await;
+        let path = dead_letter_path("missing-dir").join("dead.jsonl");
+        let outcome = forwarder(url, 0, Some(path))
+            .forward(&event())
+            .await
+            .unwrap();
+        assert_eq!(outcome, Delivery::Dropped);
+    }
+}